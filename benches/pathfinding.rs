@@ -0,0 +1,74 @@
+// compares the flat A star algorithm with the hierarchical pathfinder
+// run with: cargo bench --bench pathfinding
+
+use std::time::Instant;
+use d7engine::core::math::pathfinding::{astar, Maze};
+use d7engine::core::math::pathfinding::hierarchical::Hierarchy;
+
+// an open map with vertical walls,
+// every wall has a gap at alternating ends
+struct Map {
+    size: usize,
+}
+
+impl Maze for Map {
+    fn width(&self) -> usize {
+        self.size
+    }
+
+    fn height(&self) -> usize {
+        self.size
+    }
+
+    fn solid(&self, x: usize, y: usize) -> bool {
+        if x % 16 != 8 {
+            return false;
+        }
+
+        let gap = if (x / 16).is_multiple_of(2) { self.size - 1 } else { 0 };
+        y != gap
+    }
+}
+
+fn main() {
+    // on the small maps there are only a few chunks and nothing to skip,
+    // the bigger ones show the long distance case both can still finish
+    for size in [16, 32, 128, 256] {
+        let map = Map {size};
+        let start = (0, 0);
+        let end = (size - 1, size - 1);
+
+        let now = Instant::now();
+        let flat = astar(&map, start, end);
+        let flat_time = now.elapsed();
+
+        let now = Instant::now();
+        let hierarchy = Hierarchy::new(&map, 16);
+        let build_time = now.elapsed();
+
+        let now = Instant::now();
+        let hierarchical = hierarchy.find(&map, start, end);
+        let find_time = now.elapsed();
+
+        println!(
+            "{}x{}: astar {:?} ({} cells), hierarchical {:?} ({} cells), build {:?}",
+            size, size,
+            flat_time, flat.map_or(0, |path| path.len()),
+            find_time, hierarchical.map_or(0, |path| path.len()),
+            build_time,
+        );
+    }
+
+    // the flat algorithm does not finish in reasonable time on this size
+    let map = Map {size: 2048};
+    let now = Instant::now();
+    let hierarchy = Hierarchy::new(&map, 32);
+    let build_time = now.elapsed();
+
+    let now = Instant::now();
+    let path = hierarchy.find(&map, (0, 0), (2047, 2047));
+    println!(
+        "2048x2048: hierarchical {:?} ({} cells), build {:?}",
+        now.elapsed(), path.map_or(0, |path| path.len()), build_time,
+    );
+}
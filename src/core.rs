@@ -8,6 +8,10 @@ pub mod math;
 pub mod project;
 pub mod shader;
 pub mod default;
+pub mod grid;
+pub mod tween;
+pub mod animation;
+pub mod procgen;
 
 pub use crate::core::project::{Config, Runtime, Draw};
 pub use crate::core::math::transform::Transform;
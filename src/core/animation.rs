@@ -0,0 +1,275 @@
+pub mod machine;
+
+use crate::core::resource::animation::{Clip, Frame, PlayMode};
+use crate::core::shader::{shader::Shader, instanced::InstancedShader};
+use crate::core::shader::object::TextureCoordinate;
+
+/*
+plays a clip of an animation
+
+the animator is advanced with the delta time of a frame
+and returns the events of the frames that were shown,
+afterwards the texcoord of the current frame can be applied
+to a Shader or an instance of an InstancedShader
+*/
+pub struct Animator {
+    clip: Clip,
+    frame: usize,
+    // seconds the current frame is shown
+    time: f32,
+    // seconds since the clip was started
+    elapsed: f32,
+    forward: bool,
+    playing: bool,
+    finished: bool,
+    // the event of the first frame is fired with the first update
+    started: bool,
+    pub speed: f32,
+}
+
+impl Animator {
+    // create an animator that plays the clip
+    pub fn new(clip: Clip) -> Animator {
+        Animator {
+            clip,
+            frame: 0,
+            time: 0.0,
+            elapsed: 0.0,
+            forward: true,
+            playing: true,
+            finished: false,
+            started: false,
+            speed: 1.0,
+        }
+    }
+
+    // play another clip from the start, the same clip keeps playing
+    pub fn play(&mut self, clip: &Clip) {
+        if clip.name() != self.clip.name() {
+            self.clip = clip.clone();
+            self.restart();
+        }
+
+        self.playing = true;
+    }
+
+    // play the current clip from the first frame
+    pub fn restart(&mut self) {
+        self.frame = 0;
+        self.time = 0.0;
+        self.elapsed = 0.0;
+        self.forward = true;
+        self.finished = false;
+        self.started = false;
+        self.playing = true;
+    }
+
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    pub fn resume(&mut self) {
+        self.playing = true;
+    }
+
+    /*
+    advance the animation by delta seconds
+    returns the events of all frames that started to show,
+    a big delta can skip frames but their events are still returned
+    */
+    pub fn update(&mut self, delta: f32) -> Vec<String> {
+        let mut events = vec![];
+
+        if !self.started {
+            self.started = true;
+            self.push_event(&mut events);
+        }
+
+        if !self.playing || self.finished {
+            return events;
+        }
+
+        let delta = (delta * self.speed).max(0.0);
+        self.time += delta;
+        self.elapsed += delta;
+
+        // a clip always has frames with a duration above 0, see Clip::new
+        while self.time >= self.frame().duration() {
+            self.time -= self.frame().duration();
+
+            if !self.advance() {
+                self.finished = true;
+                self.time = 0.0;
+                break;
+            }
+
+            self.push_event(&mut events);
+        }
+
+        events
+    }
+
+    // the clip that is played
+    pub fn clip(&self) -> &Clip {
+        &self.clip
+    }
+
+    // the frame that is shown
+    pub fn frame(&self) -> &Frame {
+        &self.clip.frames()[self.frame]
+    }
+
+    // the index of the frame that is shown
+    pub fn frame_index(&self) -> usize {
+        self.frame
+    }
+
+    pub fn texcoord(&self) -> TextureCoordinate {
+        self.frame().texcoord()
+    }
+
+    /*
+    the played time relative to the duration of the clip,
+    1.0 means the clip was played once, a looping clip goes above 1.0
+    */
+    pub fn normalized_time(&self) -> f32 {
+        self.elapsed / self.clip.duration()
+    }
+
+    // true if a clip that plays once reached its end
+    pub fn finished(&self) -> bool {
+        self.finished
+    }
+
+    pub fn playing(&self) -> bool {
+        self.playing && !self.finished
+    }
+
+    // set the texcoord of the current frame to a shader
+    pub fn apply(&self, shader: &mut Shader) {
+        if shader.texcoord() != self.texcoord() {
+            shader.set_texcoord(self.texcoord());
+        }
+    }
+
+    // set the texcoord of the current frame to an instance of an instanced shader
+    pub fn apply_instance(&self, shader: &mut InstancedShader, i: usize) -> Result<(), String> {
+        if shader.texcoord(i)? != self.texcoord() {
+            shader.set_texcoord(i, self.texcoord())?;
+        }
+
+        Ok(())
+    }
+
+    // go to the next frame, false if the clip is at its end
+    fn advance(&mut self) -> bool {
+        let last = self.clip.frames().len() - 1;
+
+        match self.clip.mode() {
+            PlayMode::Loop => {
+                self.frame = if self.frame == last { 0 } else { self.frame + 1 };
+                true
+            },
+            PlayMode::Once => {
+                if self.frame == last {
+                    return false;
+                }
+
+                self.frame += 1;
+                true
+            },
+            PlayMode::PingPong => {
+                if last == 0 {
+                    return true;
+                }
+
+                if self.forward && self.frame == last || !self.forward && self.frame == 0 {
+                    self.forward = !self.forward;
+                }
+
+                if self.forward {
+                    self.frame += 1;
+                } else {
+                    self.frame -= 1;
+                }
+                true
+            },
+        }
+    }
+
+    fn push_event(&self, events: &mut Vec<String>) {
+        if let Some(event) = self.frame().event() {
+            events.push(event.to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a clip with 3 frames of 0.1 seconds, the second frame has an event
+    pub fn clip(name: &str, mode: PlayMode) -> Clip {
+        let frames = (0..3)
+            .map(|i| Frame::new(&format!("{}_{}", name, i), [i as f32; 8], 0.1, if i == 1 { Some("step") } else { None }))
+            .collect();
+
+        Clip::new(name, mode, frames).unwrap()
+    }
+
+    // the frames shown after every update of 0.1 seconds
+    fn frames(animator: &mut Animator, updates: usize) -> Vec<usize> {
+        (0..updates)
+            .map(|_| {
+                animator.update(0.1);
+                animator.frame_index()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_modes() {
+        // step a bit above the frame duration to avoid rounding errors
+        let mut animator = Animator::new(clip("walk", PlayMode::Loop));
+        animator.update(0.0001);
+        assert_eq!(vec![1, 2, 0, 1], frames(&mut animator, 4));
+
+        let mut animator = Animator::new(clip("bounce", PlayMode::PingPong));
+        animator.update(0.0001);
+        assert_eq!(vec![1, 2, 1, 0, 1, 2], frames(&mut animator, 6));
+
+        let mut animator = Animator::new(clip("die", PlayMode::Once));
+        animator.update(0.0001);
+        assert_eq!(vec![1, 2, 2, 2], frames(&mut animator, 4));
+        assert!(animator.finished());
+        assert!(!animator.playing());
+        assert_eq!([2.0; 8], animator.texcoord());
+    }
+
+    #[test]
+    fn test_events_and_play() {
+        let mut animator = Animator::new(clip("walk", PlayMode::Loop));
+        assert!(animator.update(0.05).is_empty());
+        assert_eq!(vec!["step".to_string()], animator.update(0.1));
+
+        // a big step skips frames but keeps the events
+        assert_eq!(vec!["step".to_string(), "step".to_string()], animator.update(0.6));
+        assert!((animator.normalized_time() - 0.75 / 0.3).abs() < 0.001);
+
+        // the same clip keeps playing, another clip starts from the start
+        let frame = animator.frame_index();
+        animator.play(&clip("walk", PlayMode::Loop));
+        assert_eq!(frame, animator.frame_index());
+        animator.play(&clip("run", PlayMode::Loop));
+        assert_eq!(0, animator.frame_index());
+        assert_eq!("run_0", animator.frame().name());
+
+        animator.pause();
+        animator.update(1.0);
+        assert_eq!(0, animator.frame_index());
+        animator.resume();
+        animator.speed = 2.0;
+        animator.update(0.06);
+        assert_eq!(1, animator.frame_index());
+    }
+}
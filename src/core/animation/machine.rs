@@ -0,0 +1,294 @@
+use std::collections::HashMap;
+use crate::core::animation::Animator;
+use crate::core::resource::animation::Clip;
+
+// the value of a parameter of a state machine
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Parameter {
+    Bool(bool),
+    Float(f32),
+    // true until a transition uses it
+    Trigger(bool),
+}
+
+// a condition on a parameter, a missing parameter is never true
+#[derive(Clone, PartialEq, Debug)]
+pub enum Condition {
+    Bool(String, bool),
+    Greater(String, f32),
+    Less(String, f32),
+    Trigger(String),
+}
+
+/*
+a switch from one state to another
+
+all conditions have to be true, with an exit time the
+current clip has to be played that long first (1.0 is once).
+a transition without a from state can start in any state
+*/
+#[derive(Clone, Debug)]
+pub struct Transition {
+    from: Option<String>,
+    to: String,
+    conditions: Vec<Condition>,
+    exit_time: Option<f32>,
+}
+
+impl Transition {
+    pub fn new(from: &str, to: &str) -> Transition {
+        Transition {from: Some(from.to_string()), to: to.to_string(), conditions: vec![], exit_time: None}
+    }
+
+    // a transition from any state except to itself
+    pub fn any(to: &str) -> Transition {
+        Transition {from: None, to: to.to_string(), conditions: vec![], exit_time: None}
+    }
+
+    // add a condition
+    pub fn when(mut self, condition: Condition) -> Self {
+        self.conditions.push(condition);
+        self
+    }
+
+    // the part of the current clip that has to be played first
+    pub fn exit_time(mut self, normalized_time: f32) -> Self {
+        self.exit_time = Some(normalized_time);
+        self
+    }
+}
+
+/*
+Animation state machine.
+
+every state plays a clip, the transitions switch between the states
+when their conditions on the parameters are true. the new clip starts
+with its first frame, there is no blending between two clips.
+the machine only needs the delta time, no window or shader
+*/
+pub struct StateMachine {
+    states: Vec<(String, Clip)>,
+    transitions: Vec<Transition>,
+    parameters: HashMap<String, Parameter>,
+    current: usize,
+    animator: Animator,
+}
+
+impl StateMachine {
+    // create a machine that starts in the state name
+    pub fn new(name: &str, clip: Clip) -> StateMachine {
+        StateMachine {
+            states: vec![(name.to_string(), clip.clone())],
+            transitions: vec![],
+            parameters: HashMap::new(),
+            current: 0,
+            animator: Animator::new(clip),
+        }
+    }
+
+    // add a state, a state with the same name gets the new clip
+    pub fn add_state(&mut self, name: &str, clip: Clip) {
+        match self.find(name) {
+            Some(i) => self.states[i].1 = clip,
+            None => self.states.push((name.to_string(), clip)),
+        }
+    }
+
+    // add a transition between existing states,
+    // transitions added first are checked first
+    pub fn add_transition(&mut self, transition: Transition) -> Result<(), String> {
+        if let Some(from) = &transition.from {
+            self.index(from)?;
+        }
+        self.index(&transition.to)?;
+
+        self.transitions.push(transition);
+        Ok(())
+    }
+
+    pub fn set_bool(&mut self, name: &str, value: bool) {
+        self.parameters.insert(name.to_string(), Parameter::Bool(value));
+    }
+
+    pub fn set_float(&mut self, name: &str, value: f32) {
+        self.parameters.insert(name.to_string(), Parameter::Float(value));
+    }
+
+    // set a trigger, it stays set until a transition uses it
+    pub fn trigger(&mut self, name: &str) {
+        self.parameters.insert(name.to_string(), Parameter::Trigger(true));
+    }
+
+    pub fn reset_trigger(&mut self, name: &str) {
+        self.parameters.insert(name.to_string(), Parameter::Trigger(false));
+    }
+
+    pub fn parameter(&self, name: &str) -> Option<Parameter> {
+        self.parameters.get(name).copied()
+    }
+
+    /*
+    advance the clip by delta seconds, then switch the state
+    if a transition is possible. at most one transition is
+    taken per update. returns the events of the clip
+    */
+    pub fn update(&mut self, delta: f32) -> Vec<String> {
+        let events = self.animator.update(delta);
+
+        if let Some(i) = self.next_transition() {
+            let transition = self.transitions[i].clone();
+
+            // use up the triggers of the transition
+            for condition in transition.conditions.iter() {
+                if let Condition::Trigger(name) = condition {
+                    self.reset_trigger(name);
+                }
+            }
+
+            if let Some(to) = self.find(&transition.to) {
+                self.enter(to);
+            }
+        }
+
+        events
+    }
+
+    // switch to a state without a transition
+    pub fn set_state(&mut self, name: &str) -> Result<(), String> {
+        let i = self.index(name)?;
+        self.enter(i);
+        Ok(())
+    }
+
+    // the name of the current state
+    pub fn state(&self) -> &str {
+        &self.states[self.current].0
+    }
+
+    // the animator of the current clip, used to draw the frame
+    pub fn animator(&self) -> &Animator {
+        &self.animator
+    }
+
+    fn enter(&mut self, i: usize) {
+        self.current = i;
+        self.animator.play(&self.states[i].1);
+        self.animator.restart();
+    }
+
+    // the first transition that can be taken
+    fn next_transition(&self) -> Option<usize> {
+        let state = self.state();
+
+        self.transitions.iter().position(|transition| {
+            let from = match &transition.from {
+                Some(from) => from == state,
+                None => transition.to != state,
+            };
+
+            let exit = match transition.exit_time {
+                Some(exit_time) => self.animator.normalized_time() >= exit_time,
+                None => true,
+            };
+
+            from && exit && transition.conditions.iter().all(|condition| self.fulfilled(condition))
+        })
+    }
+
+    fn fulfilled(&self, condition: &Condition) -> bool {
+        match condition {
+            Condition::Bool(name, value) => self.parameter(name) == Some(Parameter::Bool(*value)),
+            Condition::Greater(name, value) => matches!(self.parameter(name), Some(Parameter::Float(v)) if v > *value),
+            Condition::Less(name, value) => matches!(self.parameter(name), Some(Parameter::Float(v)) if v < *value),
+            Condition::Trigger(name) => self.parameter(name) == Some(Parameter::Trigger(true)),
+        }
+    }
+
+    fn find(&self, name: &str) -> Option<usize> {
+        self.states.iter().position(|(other, _)| other == name)
+    }
+
+    fn index(&self, name: &str) -> Result<usize, String> {
+        self.find(name).ok_or(format!("Could not get state '{}'.", name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::animation::tests::clip;
+    use crate::core::resource::animation::PlayMode;
+
+    // a character with idle, run, jump and attack
+    fn character() -> StateMachine {
+        let mut machine = StateMachine::new("idle", clip("idle", PlayMode::Loop));
+        machine.add_state("run", clip("run", PlayMode::Loop));
+        machine.add_state("jump", clip("jump", PlayMode::Once));
+        machine.add_state("attack", clip("attack", PlayMode::Once));
+
+        let transitions = [
+            Transition::new("idle", "run").when(Condition::Greater("speed".to_string(), 0.1)),
+            Transition::new("run", "idle").when(Condition::Less("speed".to_string(), 0.1)),
+            Transition::any("jump").when(Condition::Bool("grounded".to_string(), false)),
+            Transition::new("jump", "idle").when(Condition::Bool("grounded".to_string(), true)).exit_time(1.0),
+            Transition::new("idle", "attack").when(Condition::Trigger("attack".to_string())),
+            Transition::new("attack", "idle").exit_time(1.0),
+        ];
+
+        for transition in transitions {
+            machine.add_transition(transition).unwrap();
+        }
+
+        machine.set_bool("grounded", true);
+        machine
+    }
+
+    #[test]
+    fn test_transitions() {
+        let mut machine = character();
+        machine.update(0.1);
+        assert_eq!("idle", machine.state());
+
+        machine.set_float("speed", 3.0);
+        machine.update(0.1);
+        assert_eq!("run", machine.state());
+        assert_eq!(0, machine.animator().frame_index());
+
+        // any state
+        machine.set_bool("grounded", false);
+        machine.update(0.1);
+        assert_eq!("jump", machine.state());
+
+        // landing waits for the jump clip to end
+        machine.set_bool("grounded", true);
+        machine.set_float("speed", 0.0);
+        machine.update(0.15);
+        assert_eq!("jump", machine.state());
+        machine.update(0.2);
+        assert_eq!("idle", machine.state());
+    }
+
+    #[test]
+    fn test_trigger() {
+        let mut machine = character();
+        machine.trigger("attack");
+        machine.update(0.01);
+        assert_eq!("attack", machine.state());
+        assert_eq!(Some(Parameter::Trigger(false)), machine.parameter("attack"));
+
+        // the events of the attack clip
+        machine.update(0.01);
+        assert_eq!(vec!["step".to_string()], machine.update(0.1));
+        machine.update(0.3);
+        assert_eq!("idle", machine.state());
+
+        // the trigger was used up
+        machine.update(0.1);
+        assert_eq!("idle", machine.state());
+
+        assert!(machine.add_transition(Transition::new("idle", "fly")).is_err());
+        assert!(machine.set_state("fly").is_err());
+        machine.set_state("run").unwrap();
+        assert_eq!("run", machine.state());
+    }
+}
@@ -0,0 +1,436 @@
+use crate::core::math::pathfinding::Maze;
+use crate::core::resource::tilemap::{TileMap, TileDataRotation};
+use crate::core::shader::object::TextureCoordinate;
+
+// the 4 adjacent cells
+const NEIGHBOURS_4: [(isize, isize); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+// the 4 adjacent and the 4 diagonal cells
+const NEIGHBOURS_8: [(isize, isize); 8] = [
+    (0, -1), (1, -1), (1, 0), (1, 1),
+    (0, 1), (-1, 1), (-1, 0), (-1, -1),
+];
+
+/**
+Implement Solid for the cells of a Grid,
+so the Grid can be used as a Maze for the pathfinding.
+*/
+pub trait Solid {
+    /// can the cell not be walked on
+    fn solid(&self) -> bool;
+}
+
+/*
+a 2D array stored row by row
+
+all methods that take a position check the bounds,
+cells outside of the grid are None or ignored
+*/
+#[derive(Clone, PartialEq, Debug)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Clone> Grid<T> {
+    // create a grid where every cell is value
+    pub fn new(width: usize, height: usize, value: T) -> Grid<T> {
+        Grid {width, height, cells: vec![value; width * height]}
+    }
+
+    // create a grid from the cells row by row
+    pub fn from_vec(width: usize, height: usize, cells: Vec<T>) -> Result<Grid<T>, String> {
+        if cells.len() != width * height {
+            return Err(format!("A grid of {}x{} needs {} cells, got {}.", width, height, width * height, cells.len()));
+        }
+
+        Ok(Grid {width, height, cells})
+    }
+
+    // create a grid from rows, all rows need the same length
+    pub fn from_rows(rows: &[Vec<T>]) -> Result<Grid<T>, String> {
+        let width = rows.first().map(|row| row.len()).unwrap_or(0);
+
+        if rows.iter().any(|row| row.len() != width) {
+            return Err("All rows of a grid need the same length.".to_string());
+        }
+
+        Grid::from_vec(width, rows.len(), rows.concat())
+    }
+
+    // create a grid from rows of tile names
+    pub fn from_names<F: Fn(&str) -> Result<T, String>>(rows: &[Vec<String>], cell: F) -> Result<Grid<T>, String> {
+        let mut converted = vec![];
+
+        for row in rows {
+            let mut cells = vec![];
+            for name in row {
+                cells.push(cell(name)?);
+            }
+            converted.push(cells);
+        }
+
+        Grid::from_rows(&converted)
+    }
+
+    // returns the grid as rows
+    pub fn rows(&self) -> Vec<Vec<T>> {
+        if self.width == 0 {
+            return vec![vec![]; self.height];
+        }
+
+        self.cells.chunks(self.width).map(|row| row.to_vec()).collect()
+    }
+
+    // set every cell to value
+    pub fn fill(&mut self, value: T) {
+        for cell in self.cells.iter_mut() {
+            *cell = value.clone();
+        }
+    }
+
+    /*
+    copy the region x, y, width, height into a new grid,
+    the region is cut at the border of the grid
+    */
+    pub fn copy(&self, x: usize, y: usize, width: usize, height: usize) -> Grid<T> {
+        let width = width.min(self.width.saturating_sub(x));
+        let height = height.min(self.height.saturating_sub(y));
+        // a region outside of the grid is empty
+        if width == 0 || height == 0 {
+            return Grid {width: 0, height: 0, cells: vec![]};
+        }
+
+        let mut cells = Vec::with_capacity(width * height);
+
+        for row in y..y + height {
+            let start = row * self.width + x;
+            cells.extend_from_slice(&self.cells[start..start + width]);
+        }
+
+        Grid {width, height, cells}
+    }
+
+    // paste another grid at x, y, cells outside of the grid are ignored
+    pub fn paste(&mut self, other: &Grid<T>, x: usize, y: usize) {
+        for (ox, oy, value) in other.iter() {
+            self.set(x + ox, y + oy, value.clone());
+        }
+    }
+
+    /*
+    change the size of the grid, the cells keep their position
+    from the top left and new cells are set to value
+    */
+    pub fn resize(&mut self, width: usize, height: usize, value: T) {
+        let mut resized = Grid::new(width, height, value);
+        resized.paste(self, 0, 0);
+        *self = resized;
+    }
+}
+
+impl<T> Grid<T> {
+    // the number of cells in x direction
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    // the number of cells in y direction
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    // true if x, y is inside of the grid
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height
+    }
+
+    // returns the cell x, y or None if it is outside of the grid
+    pub fn get(&self, x: usize, y: usize) -> Option<&T> {
+        if self.contains(x, y) {
+            Some(&self.cells[y * self.width + x])
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut T> {
+        if self.contains(x, y) {
+            Some(&mut self.cells[y * self.width + x])
+        } else {
+            None
+        }
+    }
+
+    // set the cell x, y, cells outside of the grid are ignored
+    pub fn set(&mut self, x: usize, y: usize, value: T) {
+        if let Some(cell) = self.get_mut(x, y) {
+            *cell = value;
+        }
+    }
+
+    // all cells with their position, row by row
+    pub fn iter(&self) -> impl Iterator<Item = (usize, usize, &T)> {
+        let width = self.width;
+        self.cells.iter().enumerate().map(move |(i, cell)| (i % width, i / width, cell))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (usize, usize, &mut T)> {
+        let width = self.width;
+        self.cells.iter_mut().enumerate().map(move |(i, cell)| (i % width, i / width, cell))
+    }
+
+    // the positions of all cells where f is true
+    pub fn positions<F: Fn(&T) -> bool>(&self, f: F) -> Vec<(usize, usize)> {
+        self.iter().filter(|(_, _, cell)| f(cell)).map(|(x, y, _)| (x, y)).collect()
+    }
+
+    // create a new grid of the same size by converting every cell
+    pub fn map<U, F: Fn(&T) -> U>(&self, f: F) -> Grid<U> {
+        Grid {width: self.width, height: self.height, cells: self.cells.iter().map(f).collect()}
+    }
+
+    // the up to 4 adjacent cells that are inside of the grid
+    pub fn neighbours_4(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.offsets(x, y, &NEIGHBOURS_4)
+    }
+
+    // the up to 8 adjacent and diagonal cells that are inside of the grid
+    pub fn neighbours_8(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.offsets(x, y, &NEIGHBOURS_8)
+    }
+
+    /*
+    all cells that can be reached from start over the 4 adjacent cells
+    where f is true (flood fill), start is included if f is true for it
+    */
+    pub fn flood_fill<F: Fn(&T) -> bool>(&self, start: (usize, usize), f: F) -> Vec<(usize, usize)> {
+        let mut visited = vec![false; self.cells.len()];
+        self.region(start, &f, &mut visited)
+    }
+
+    /*
+    connected-component labeling
+    every group of cells where f is true that are connected over
+    the 4 adjacent cells gets its own label, starting with 0.
+    returns the labels and the number of groups
+    */
+    pub fn label<F: Fn(&T) -> bool>(&self, f: F) -> (Grid<Option<usize>>, usize) {
+        let mut labels = Grid::new(self.width, self.height, None);
+        let regions = self.regions(f);
+
+        for (label, region) in regions.iter().enumerate() {
+            for (x, y) in region {
+                labels.set(*x, *y, Some(label));
+            }
+        }
+
+        (labels, regions.len())
+    }
+
+    // all groups of connected cells where f is true
+    pub fn regions<F: Fn(&T) -> bool>(&self, f: F) -> Vec<Vec<(usize, usize)>> {
+        let mut visited = vec![false; self.cells.len()];
+        let mut regions = vec![];
+
+        for i in 0..self.cells.len() {
+            if visited[i] {
+                continue;
+            }
+
+            let region = self.region((i % self.width, i / self.width), &f, &mut visited);
+            if !region.is_empty() {
+                regions.push(region);
+            }
+        }
+
+        regions
+    }
+
+    /*
+    convert the grid to rows of tile names,
+    the same format the wave function collapse creates
+    */
+    pub fn to_names<F: Fn(&T) -> String>(&self, name: F) -> Vec<Vec<String>> {
+        (0..self.height)
+            .map(|y| (0..self.width).map(|x| name(&self.cells[y * self.width + x])).collect())
+            .collect()
+    }
+
+    /*
+    the texture coordinates of every cell in a tilemap
+    they can be used for an instanced texture to draw the grid
+    */
+    pub fn texcoords<F: Fn(&T) -> String>(&self, tilemap: &TileMap, name: F) -> Result<Grid<TextureCoordinate>, String> {
+        let mut cells = Vec::with_capacity(self.cells.len());
+
+        for cell in self.cells.iter() {
+            let tile = tilemap.get(&name(cell))?;
+            cells.push(tile.texcoord(&TileDataRotation::Bottom));
+        }
+
+        Ok(Grid {width: self.width, height: self.height, cells})
+    }
+
+    fn offsets<'a>(&'a self, x: usize, y: usize, offsets: &'a [(isize, isize)]) -> impl Iterator<Item = (usize, usize)> + 'a {
+        offsets.iter().filter_map(move |(dx, dy)| {
+            let nx = x as isize + dx;
+            let ny = y as isize + dy;
+            if nx < 0 || ny < 0 || !self.contains(nx as usize, ny as usize) {
+                None
+            } else {
+                Some((nx as usize, ny as usize))
+            }
+        })
+    }
+
+    fn region<F: Fn(&T) -> bool>(&self, start: (usize, usize), f: &F, visited: &mut [bool]) -> Vec<(usize, usize)> {
+        let mut region = vec![];
+
+        match self.get(start.0, start.1) {
+            Some(cell) if f(cell) => (),
+            _ => return region,
+        }
+
+        let mut open = vec![start];
+        visited[start.1 * self.width + start.0] = true;
+
+        while let Some((x, y)) = open.pop() {
+            region.push((x, y));
+
+            for (nx, ny) in self.neighbours_4(x, y) {
+                let i = ny * self.width + nx;
+                if !visited[i] && f(&self.cells[i]) {
+                    visited[i] = true;
+                    open.push((nx, ny));
+                }
+            }
+        }
+
+        region
+    }
+}
+
+impl<T: Solid> Maze for Grid<T> {
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn solid(&self, x: usize, y: usize) -> bool {
+        match self.get(x, y) {
+            Some(cell) => cell.solid(),
+            None => true,
+        }
+    }
+}
+
+impl Solid for bool {
+    // true is a wall
+    fn solid(&self) -> bool {
+        *self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::math::pathfinding::astar;
+
+    fn example() -> Grid<char> {
+        let rows: Vec<Vec<char>> = [
+            "..#..",
+            "..#..",
+            "###..",
+            "....#",
+        ].iter().map(|row| row.chars().collect()).collect();
+        Grid::from_rows(&rows).unwrap()
+    }
+
+    #[test]
+    fn test_get_set() {
+        let mut grid = example();
+        assert_eq!((5, 4), (grid.width(), grid.height()));
+        assert_eq!(Some(&'#'), grid.get(2, 0));
+        assert_eq!(None, grid.get(5, 0));
+
+        grid.set(0, 0, 'x');
+        grid.set(9, 9, 'x');
+        assert_eq!(Some(&'x'), grid.get(0, 0));
+        assert_eq!(1, grid.positions(|cell| *cell == 'x').len());
+
+        // row by row
+        let order: Vec<(usize, usize)> = grid.iter().take(6).map(|(x, y, _)| (x, y)).collect();
+        assert_eq!(vec![(0, 0), (1, 0), (2, 0), (3, 0), (4, 0), (0, 1)], order);
+
+        assert!(Grid::from_vec(2, 2, vec![0; 3]).is_err());
+        assert!(Grid::from_rows(&[vec![0; 2], vec![0; 3]]).is_err());
+    }
+
+    #[test]
+    fn test_neighbours() {
+        let grid = example();
+        assert_eq!(vec![(1, 0), (0, 1)], grid.neighbours_4(0, 0).collect::<Vec<_>>());
+        assert_eq!(3, grid.neighbours_8(0, 0).count());
+        assert_eq!(8, grid.neighbours_8(2, 2).count());
+        assert_eq!(4, grid.neighbours_4(2, 2).count());
+    }
+
+    #[test]
+    fn test_flood_fill_and_label() {
+        let grid = example();
+        let floor = |cell: &char| *cell == '.';
+
+        assert_eq!(4, grid.flood_fill((0, 0), floor).len());
+        assert_eq!(10, grid.flood_fill((4, 0), floor).len());
+        assert!(grid.flood_fill((2, 0), floor).is_empty());
+
+        let (labels, count) = grid.label(floor);
+        assert_eq!(2, count);
+        assert_eq!(Some(&Some(0)), labels.get(1, 1));
+        assert_eq!(Some(&Some(1)), labels.get(0, 3));
+        assert_eq!(Some(&None), labels.get(2, 2));
+        assert_eq!(2, grid.regions(floor).len());
+    }
+
+    #[test]
+    fn test_copy_paste_resize() {
+        let mut grid = example();
+        let part = grid.copy(3, 2, 5, 5);
+        assert_eq!((2, 2), (part.width(), part.height()));
+        assert_eq!(vec![vec!['.', '.'], vec!['.', '#']], part.rows());
+
+        // the origin is outside of the grid
+        let empty = grid.copy(100, 0, 1, 1);
+        assert_eq!((0, 0), (empty.width(), empty.height()));
+        assert!(grid.copy(0, 100, 1, 1).rows().is_empty());
+
+        grid.paste(&part, 0, 0);
+        assert_eq!(Some(&'#'), grid.get(1, 1));
+
+        grid.resize(6, 2, '+');
+        assert_eq!(vec!['.', '.', '#', '.', '.', '+'], grid.rows()[0]);
+        assert_eq!(2, grid.height());
+    }
+
+    #[test]
+    fn test_maze_and_names() {
+        let grid = example().map(|cell| *cell == '#');
+        assert!(astar(&grid, (0, 0), (4, 0)).is_none());
+        assert!(astar(&grid, (0, 3), (4, 0)).is_some());
+
+        let names = grid.to_names(|wall| if *wall { "wall".to_string() } else { "floor".to_string() });
+        assert_eq!("wall", names[2][0]);
+
+        let back = Grid::from_names(&names, |name| match name {
+            "wall" => Ok(true),
+            "floor" => Ok(false),
+            _ => Err(format!("Unknown tile '{}'.", name)),
+        }).unwrap();
+        assert_eq!(grid, back);
+    }
+}
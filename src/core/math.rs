@@ -2,4 +2,12 @@ pub mod mvp;
 pub mod transform;
 pub mod collision;
 pub mod pathfinding;
-pub mod linear;
\ No newline at end of file
+pub mod linear;
+pub mod path;
+pub mod fov;
+pub mod noise;
+pub mod hex;
+pub mod iso;
+pub mod easing;
+pub mod curve;
+pub mod shape;
\ No newline at end of file
@@ -0,0 +1,471 @@
+use crate::core::color::Color;
+use crate::core::math::collision;
+use crate::core::math::path::catmull_rom;
+use crate::core::shader::data::ObjectData;
+
+/**
+Implement Curve for a 2D curve,
+so it can be measured, searched and drawn.
+*/
+pub trait Curve {
+    /// the point at t, the curve starts at 0.0 and ends at 1.0
+    fn point(&self, t: f32) -> (f32, f32);
+    /// the direction and speed of the curve at t
+    fn derivative(&self, t: f32) -> (f32, f32);
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct QuadraticBezier {
+    pub p0: (f32, f32),
+    pub p1: (f32, f32),
+    pub p2: (f32, f32),
+}
+
+impl QuadraticBezier {
+    // the curve goes from p0 to p2 and is pulled towards p1
+    pub fn new(p0: (f32, f32), p1: (f32, f32), p2: (f32, f32)) -> QuadraticBezier {
+        QuadraticBezier {p0, p1, p2}
+    }
+}
+
+impl Curve for QuadraticBezier {
+    fn point(&self, t: f32) -> (f32, f32) {
+        let inv = 1.0 - t;
+        let calc = |a: f32, b: f32, c: f32| inv * inv * a + 2.0 * inv * t * b + t * t * c;
+        (calc(self.p0.0, self.p1.0, self.p2.0), calc(self.p0.1, self.p1.1, self.p2.1))
+    }
+
+    fn derivative(&self, t: f32) -> (f32, f32) {
+        let calc = |a: f32, b: f32, c: f32| 2.0 * (1.0 - t) * (b - a) + 2.0 * t * (c - b);
+        (calc(self.p0.0, self.p1.0, self.p2.0), calc(self.p0.1, self.p1.1, self.p2.1))
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct CubicBezier {
+    pub p0: (f32, f32),
+    pub p1: (f32, f32),
+    pub p2: (f32, f32),
+    pub p3: (f32, f32),
+}
+
+impl CubicBezier {
+    // the curve goes from p0 to p3, p1 and p2 are the control points
+    pub fn new(p0: (f32, f32), p1: (f32, f32), p2: (f32, f32), p3: (f32, f32)) -> CubicBezier {
+        CubicBezier {p0, p1, p2, p3}
+    }
+}
+
+impl Curve for CubicBezier {
+    fn point(&self, t: f32) -> (f32, f32) {
+        let inv = 1.0 - t;
+        let calc = |a: f32, b: f32, c: f32, d: f32| {
+            inv * inv * inv * a + 3.0 * inv * inv * t * b + 3.0 * inv * t * t * c + t * t * t * d
+        };
+        (
+            calc(self.p0.0, self.p1.0, self.p2.0, self.p3.0),
+            calc(self.p0.1, self.p1.1, self.p2.1, self.p3.1),
+        )
+    }
+
+    fn derivative(&self, t: f32) -> (f32, f32) {
+        let inv = 1.0 - t;
+        let calc = |a: f32, b: f32, c: f32, d: f32| {
+            3.0 * inv * inv * (b - a) + 6.0 * inv * t * (c - b) + 3.0 * t * t * (d - c)
+        };
+        (
+            calc(self.p0.0, self.p1.0, self.p2.0, self.p3.0),
+            calc(self.p0.1, self.p1.1, self.p2.1, self.p3.1),
+        )
+    }
+}
+
+/*
+a spline that goes through all of its points,
+a closed spline connects the last point with the first one
+*/
+#[derive(Clone, Debug)]
+pub struct CatmullRom {
+    pub points: Vec<(f32, f32)>,
+    pub closed: bool,
+}
+
+impl CatmullRom {
+    pub fn new(points: Vec<(f32, f32)>, closed: bool) -> CatmullRom {
+        CatmullRom {points, closed}
+    }
+
+    // the 4 points of a segment, the outer points of an open spline are duplicated
+    fn segment(&self, i: usize) -> [(f32, f32); 4] {
+        let n = self.points.len() as isize;
+        let get = |k: isize| {
+            let k = if self.closed { k.rem_euclid(n) } else { k.clamp(0, n - 1) };
+            self.points[k as usize]
+        };
+        let i = i as isize;
+        [get(i - 1), get(i), get(i + 1), get(i + 2)]
+    }
+
+    fn segments(&self) -> usize {
+        match self.points.len() {
+            0 | 1 => 0,
+            n if self.closed => n,
+            n => n - 1,
+        }
+    }
+}
+
+impl Curve for CatmullRom {
+    fn point(&self, t: f32) -> (f32, f32) {
+        match locate(self.segments(), t) {
+            Some((i, u)) => {
+                let [p0, p1, p2, p3] = self.segment(i);
+                catmull_rom(p0, p1, p2, p3, u)
+            },
+            None => self.points.first().copied().unwrap_or((0.0, 0.0)),
+        }
+    }
+
+    fn derivative(&self, t: f32) -> (f32, f32) {
+        let (i, u) = match locate(self.segments(), t) {
+            Some(located) => located,
+            None => return (0.0, 0.0),
+        };
+
+        let [p0, p1, p2, p3] = self.segment(i);
+        let scale = self.segments() as f32;
+        let calc = |a: f32, b: f32, c: f32, d: f32| {
+            0.5 * ((-a + c) + 2.0 * (2.0 * a - 5.0 * b + 4.0 * c - d) * u + 3.0 * (-a + 3.0 * b - 3.0 * c + d) * u * u) * scale
+        };
+        (calc(p0.0, p1.0, p2.0, p3.0), calc(p0.1, p1.1, p2.1, p3.1))
+    }
+}
+
+/*
+a uniform cubic b-spline, it is smoother than a catmull rom spline
+but only goes close to its points. an open spline starts
+on the first and ends on the last point
+*/
+#[derive(Clone, Debug)]
+pub struct BSpline {
+    pub points: Vec<(f32, f32)>,
+    pub closed: bool,
+}
+
+impl BSpline {
+    pub fn new(points: Vec<(f32, f32)>, closed: bool) -> BSpline {
+        BSpline {points, closed}
+    }
+
+    // the 4 points of a segment, the outer points of an open spline are tripled
+    fn segment(&self, i: usize) -> [(f32, f32); 4] {
+        let n = self.points.len() as isize;
+        let get = |k: isize| {
+            let k = if self.closed { k.rem_euclid(n) } else { (k - 2).clamp(0, n - 1) };
+            self.points[k as usize]
+        };
+        let i = i as isize;
+        [get(i), get(i + 1), get(i + 2), get(i + 3)]
+    }
+
+    fn segments(&self) -> usize {
+        match self.points.len() {
+            0 | 1 => 0,
+            n if self.closed => n,
+            n => n + 1,
+        }
+    }
+}
+
+impl Curve for BSpline {
+    fn point(&self, t: f32) -> (f32, f32) {
+        let (i, u) = match locate(self.segments(), t) {
+            Some(located) => located,
+            None => return self.points.first().copied().unwrap_or((0.0, 0.0)),
+        };
+
+        let inv = 1.0 - u;
+        let weights = [
+            inv * inv * inv / 6.0,
+            (3.0 * u * u * u - 6.0 * u * u + 4.0) / 6.0,
+            (-3.0 * u * u * u + 3.0 * u * u + 3.0 * u + 1.0) / 6.0,
+            u * u * u / 6.0,
+        ];
+        weighted(&self.segment(i), weights, 1.0)
+    }
+
+    fn derivative(&self, t: f32) -> (f32, f32) {
+        let (i, u) = match locate(self.segments(), t) {
+            Some(located) => located,
+            None => return (0.0, 0.0),
+        };
+
+        let inv = 1.0 - u;
+        let weights = [
+            -inv * inv / 2.0,
+            (3.0 * u * u - 4.0 * u) / 2.0,
+            (-3.0 * u * u + 2.0 * u + 1.0) / 2.0,
+            u * u / 2.0,
+        ];
+        weighted(&self.segment(i), weights, self.segments() as f32)
+    }
+}
+
+// the segment and the t inside of it for a t of the whole spline
+fn locate(segments: usize, t: f32) -> Option<(usize, f32)> {
+    if segments == 0 {
+        return None;
+    }
+
+    let scaled = t.clamp(0.0, 1.0) * segments as f32;
+    let i = (scaled.floor() as usize).min(segments - 1);
+    Some((i, scaled - i as f32))
+}
+
+fn weighted(points: &[(f32, f32); 4], weights: [f32; 4], scale: f32) -> (f32, f32) {
+    let mut sum = (0.0, 0.0);
+    for (point, weight) in points.iter().zip(weights) {
+        sum.0 += point.0 * weight * scale;
+        sum.1 += point.1 * weight * scale;
+    }
+    sum
+}
+
+/*
+a table of t values and the distance along the curve,
+used to travel along a curve with a constant speed.
+t does not grow evenly with the distance on most curves
+*/
+pub struct ArcLength {
+    table: Vec<(f32, f32)>,
+}
+
+impl ArcLength {
+    // measure the curve with samples straight lines
+    pub fn new(curve: &dyn Curve, samples: usize) -> ArcLength {
+        let samples = samples.max(1);
+        let mut table = vec![(0.0, 0.0)];
+        let mut previous = curve.point(0.0);
+        let mut length = 0.0;
+
+        for i in 1..=samples {
+            let t = i as f32 / samples as f32;
+            let point = curve.point(t);
+            length += collision::distance(previous.0, previous.1, point.0, point.1);
+            table.push((t, length));
+            previous = point;
+        }
+
+        ArcLength {table}
+    }
+
+    // the length of the curve
+    pub fn length(&self) -> f32 {
+        self.table[self.table.len() - 1].1
+    }
+
+    // the t where the curve has the length distance
+    pub fn t_at(&self, distance: f32) -> f32 {
+        let distance = distance.clamp(0.0, self.length());
+        let i = self.table.partition_point(|(_, d)| *d < distance);
+
+        if i == 0 {
+            return 0.0;
+        }
+
+        let (t0, d0) = self.table[i - 1];
+        let (t1, d1) = self.table[i.min(self.table.len() - 1)];
+
+        if d1 - d0 <= 0.0 {
+            t1
+        } else {
+            t0 + (t1 - t0) * (distance - d0) / (d1 - d0)
+        }
+    }
+
+    // the point after travelling distance along the curve
+    pub fn point_at(&self, curve: &dyn Curve, distance: f32) -> (f32, f32) {
+        curve.point(self.t_at(distance))
+    }
+
+    // points with the same distance to each other, the end is always included
+    pub fn points(&self, curve: &dyn Curve, spacing: f32) -> Vec<(f32, f32)> {
+        let length = self.length();
+
+        if spacing <= 0.0 || length <= 0.0 {
+            return vec![curve.point(0.0), curve.point(1.0)];
+        }
+
+        let count = (length / spacing).ceil() as usize;
+        (0..=count)
+            .map(|i| self.point_at(curve, (i as f32 * spacing).min(length)))
+            .collect()
+    }
+}
+
+/*
+the t of the point on the curve closest to point
+the curve is sampled first and then the best sample is refined
+*/
+pub fn closest(curve: &dyn Curve, point: (f32, f32), samples: usize) -> f32 {
+    let samples = samples.max(1);
+    let distance = |t: f32| {
+        let p = curve.point(t);
+        (p.0 - point.0).powi(2) + (p.1 - point.1).powi(2)
+    };
+
+    let mut best = 0.0;
+    for i in 0..=samples {
+        let t = i as f32 / samples as f32;
+        if distance(t) < distance(best) {
+            best = t;
+        }
+    }
+
+    // golden section search between the neighbour samples
+    let ratio = (5.0_f32.sqrt() - 1.0) / 2.0;
+    let step = 1.0 / samples as f32;
+    let (mut low, mut high) = ((best - step).max(0.0), (best + step).min(1.0));
+
+    for _ in 0..32 {
+        let a = high - (high - low) * ratio;
+        let b = low + (high - low) * ratio;
+
+        if distance(a) < distance(b) {
+            high = b;
+        } else {
+            low = a;
+        }
+    }
+
+    (low + high) / 2.0
+}
+
+// segments + 1 points along the curve, e.g. to draw it as a polyline
+pub fn polyline(curve: &dyn Curve, segments: usize) -> Vec<(f32, f32)> {
+    let segments = segments.max(1);
+    (0..=segments).map(|i| curve.point(i as f32 / segments as f32)).collect()
+}
+
+/*
+instances of dots along the curve for an InstancedShader::circle()
+with a spacing of half the thickness the dots look like a solid line
+*/
+pub fn dots(curve: &dyn Curve, thickness: f32, color: &Color) -> Vec<ObjectData> {
+    let arc = ArcLength::new(curve, 256);
+    let radius = thickness / 2.0;
+
+    arc.points(curve, radius)
+        .into_iter()
+        .map(|(x, y)| {
+            ObjectData {
+                color: *color,
+                dim: (thickness, thickness),
+                offset: (x - radius, y - radius),
+                ..ObjectData::default()
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn near(a: (f32, f32), b: (f32, f32), tolerance: f32) -> bool {
+        (a.0 - b.0).abs() < tolerance && (a.1 - b.1).abs() < tolerance
+    }
+
+    fn curves() -> Vec<Box<dyn Curve>> {
+        let points = vec![(0.0, 0.0), (100.0, 50.0), (200.0, -30.0), (250.0, 80.0)];
+        vec![
+            Box::new(QuadraticBezier::new((0.0, 0.0), (50.0, 100.0), (100.0, 0.0))),
+            Box::new(CubicBezier::new((0.0, 0.0), (0.0, 100.0), (100.0, 100.0), (100.0, 0.0))),
+            Box::new(CatmullRom::new(points.clone(), false)),
+            Box::new(CatmullRom::new(points.clone(), true)),
+            Box::new(BSpline::new(points.clone(), false)),
+            Box::new(BSpline::new(points, true)),
+        ]
+    }
+
+    #[test]
+    fn test_points() {
+        let quadratic = QuadraticBezier::new((0.0, 0.0), (50.0, 100.0), (100.0, 0.0));
+        assert_eq!((50.0, 50.0), quadratic.point(0.5));
+
+        let cubic = CubicBezier::new((0.0, 0.0), (0.0, 100.0), (100.0, 100.0), (100.0, 0.0));
+        assert_eq!((50.0, 75.0), cubic.point(0.5));
+        assert_eq!((100.0, 0.0), cubic.point(1.0));
+
+        // catmull rom goes through every point
+        let points = vec![(0.0, 0.0), (100.0, 50.0), (200.0, -30.0), (250.0, 80.0)];
+        let spline = CatmullRom::new(points.clone(), false);
+        for (i, point) in points.iter().enumerate() {
+            assert!(near(*point, spline.point(i as f32 / 3.0), 0.01));
+        }
+
+        // an open b-spline starts and ends on its points
+        let spline = BSpline::new(points.clone(), false);
+        assert!(near(points[0], spline.point(0.0), 0.01));
+        assert!(near(points[3], spline.point(1.0), 0.01));
+
+        // a closed spline ends where it starts
+        let spline = BSpline::new(points, true);
+        assert!(near(spline.point(0.0), spline.point(1.0), 0.01));
+    }
+
+    #[test]
+    fn test_derivatives() {
+        // compare with the difference of two close points
+        for curve in curves() {
+            for t in [0.1, 0.33, 0.5, 0.8] {
+                let h = 0.001;
+                let a = curve.point(t - h);
+                let b = curve.point(t + h);
+                let numeric = ((b.0 - a.0) / (2.0 * h), (b.1 - a.1) / (2.0 * h));
+                let derivative = curve.derivative(t);
+                let tolerance = 0.01 * (derivative.0.abs() + derivative.1.abs()) + 1.0;
+                assert!(near(numeric, derivative, tolerance), "{:?} {:?}", numeric, derivative);
+            }
+        }
+    }
+
+    #[test]
+    fn test_arc_length() {
+        // a straight line with control points bunched at the start
+        let line = CubicBezier::new((0.0, 0.0), (0.0, 0.0), (0.0, 0.0), (100.0, 0.0));
+        let arc = ArcLength::new(&line, 512);
+        assert!((arc.length() - 100.0).abs() < 0.01);
+        assert!((arc.point_at(&line, 25.0).0 - 25.0).abs() < 0.1);
+
+        // equal distances along the curve, the straight distance is
+        // a bit shorter where the curve bends, the closed splines
+        // are skipped because they have tight loops
+        for (i, curve) in curves().into_iter().enumerate() {
+            if i == 3 || i == 5 {
+                continue;
+            }
+
+            let arc = ArcLength::new(curve.as_ref(), 512);
+            let points = arc.points(curve.as_ref(), 10.0);
+            for pair in points.windows(2).take(points.len() - 2) {
+                let distance = collision::distance(pair[0].0, pair[0].1, pair[1].0, pair[1].1);
+                assert!(9.5 < distance && distance < 10.01, "{}", distance);
+            }
+        }
+    }
+
+    #[test]
+    fn test_closest_and_drawing() {
+        let quadratic = QuadraticBezier::new((0.0, 0.0), (50.0, 100.0), (100.0, 0.0));
+        assert!((closest(&quadratic, (50.0, 200.0), 16) - 0.5).abs() < 0.001);
+        assert!(closest(&quadratic, (-50.0, -10.0), 16) < 0.001);
+
+        assert_eq!(11, polyline(&quadratic, 10).len());
+
+        let dots = dots(&quadratic, 4.0, &Color::rgb(255, 0, 0));
+        assert!(dots.len() > 1);
+        assert_eq!((4.0, 4.0), dots[0].dim);
+        assert_eq!((-2.0, -2.0), dots[0].offset);
+    }
+}
@@ -0,0 +1,375 @@
+use std::collections::HashSet;
+
+/**
+Implement Sight for an Object,
+so you can calculate the field of view on it.
+*/
+pub trait Sight {
+    /// the number of tiles in x direction
+    fn width(&self) -> usize;
+    /// the number of tiles in y direction
+    fn height(&self) -> usize;
+    /// does the tile x,y block the view
+    fn opaque(&self, x: usize, y: usize) -> bool;
+}
+
+/**
+Symmetric shadowcasting.
+
+returns all tiles that can be seen from origin
+within radius, walls that are seen are included.
+if a floor tile A can see a floor tile B, B can see A as well.
+*/
+pub fn field_of_view(map: &dyn Sight, origin: (usize, usize), radius: usize) -> HashSet<(usize, usize)> {
+    let mut visible = HashSet::new();
+
+    if origin.0 >= map.width() || origin.1 >= map.height() {
+        return visible;
+    }
+
+    visible.insert(origin);
+
+    for quadrant in [Quadrant::North, Quadrant::East, Quadrant::South, Quadrant::West] {
+        let first = Row {depth: 1, start: Slope::new(-1, 1), end: Slope::new(1, 1)};
+        let mut rows = vec![first];
+
+        // scan the rows of the quadrant, every
+        // gap between walls starts a new row
+        while let Some(mut row) = rows.pop() {
+            if row.depth > radius {
+                continue;
+            }
+
+            let mut previous: Option<bool> = None;
+
+            for col in row.min_col()..=row.max_col() {
+                let tile = quadrant.transform(origin, row.depth, col);
+                let wall = is_opaque(map, tile);
+
+                if let Some((x, y)) = in_bounds(map, tile) {
+                    let in_radius = (row.depth * row.depth) as isize + col * col <= (radius * radius) as isize;
+                    if in_radius && (wall || row.symmetric(col)) {
+                        visible.insert((x, y));
+                    }
+                }
+
+                if previous == Some(true) && !wall {
+                    row.start = Slope::of_tile(row.depth, col);
+                }
+
+                if previous == Some(false) && wall {
+                    let mut next = row.next();
+                    next.end = Slope::of_tile(row.depth, col);
+                    rows.push(next);
+                }
+
+                previous = Some(wall);
+            }
+
+            if previous == Some(false) {
+                rows.push(row.next());
+            }
+        }
+    }
+
+    visible
+}
+
+/// returns the tiles of a bresenham line from one tile to another
+pub fn line(from: (usize, usize), to: (usize, usize)) -> Vec<(usize, usize)> {
+    let (mut x, mut y) = (from.0 as isize, from.1 as isize);
+    let (end_x, end_y) = (to.0 as isize, to.1 as isize);
+
+    let dx = (end_x - x).abs();
+    let dy = -(end_y - y).abs();
+    let step_x = if x < end_x { 1 } else { -1 };
+    let step_y = if y < end_y { 1 } else { -1 };
+    let mut error = dx + dy;
+
+    let mut tiles = vec![];
+
+    loop {
+        tiles.push((x as usize, y as usize));
+
+        if x == end_x && y == end_y {
+            return tiles;
+        }
+
+        let double = 2 * error;
+
+        if double >= dy {
+            error += dy;
+            x += step_x;
+        }
+
+        if double <= dx {
+            error += dx;
+            y += step_y;
+        }
+    }
+}
+
+/// true if no opaque tile is between from and to,
+/// the two tiles themself can be opaque
+pub fn line_of_sight(map: &dyn Sight, from: (usize, usize), to: (usize, usize)) -> bool {
+    let tiles = line(from, to);
+
+    if tiles.len() < 3 {
+        return true;
+    }
+
+    tiles[1..tiles.len() - 1]
+        .iter()
+        .all(|(x, y)| *x < map.width() && *y < map.height() && !map.opaque(*x, *y))
+}
+
+// the state of a tile for fog of war rendering
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Visibility {
+    Hidden,
+    Explored,
+    Visible,
+}
+
+/*
+keeps track of the tiles that are visible right now
+and the tiles that were seen before (explored)
+*/
+pub struct FogOfWar {
+    width: usize,
+    height: usize,
+    explored: Vec<bool>,
+    visible: HashSet<(usize, usize)>,
+}
+
+impl FogOfWar {
+    // create a fog of war where every tile is hidden
+    pub fn new(width: usize, height: usize) -> FogOfWar {
+        FogOfWar {
+            width, height,
+            explored: vec![false; width * height],
+            visible: HashSet::new(),
+        }
+    }
+
+    // recalculate the visible tiles and remember them as explored
+    pub fn update(&mut self, map: &dyn Sight, origin: (usize, usize), radius: usize) {
+        self.visible = field_of_view(map, origin, radius);
+
+        for (x, y) in self.visible.iter() {
+            if *x < self.width && *y < self.height {
+                self.explored[y * self.width + x] = true;
+            }
+        }
+    }
+
+    // returns the visibility of a tile
+    pub fn get(&self, x: usize, y: usize) -> Visibility {
+        if self.visible.contains(&(x, y)) {
+            Visibility::Visible
+        } else if x < self.width && y < self.height && self.explored[y * self.width + x] {
+            Visibility::Explored
+        } else {
+            Visibility::Hidden
+        }
+    }
+
+    // returns the tiles that are visible right now
+    pub fn visible(&self) -> &HashSet<(usize, usize)> {
+        &self.visible
+    }
+
+    // forget all explored tiles
+    pub fn reset(&mut self) {
+        self.explored = vec![false; self.width * self.height];
+        self.visible.clear();
+    }
+}
+
+// the four directions the view is split into
+#[derive(Clone, Copy)]
+enum Quadrant {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Quadrant {
+    // convert a row and column of the quadrant to a tile
+    fn transform(&self, origin: (usize, usize), depth: usize, col: isize) -> (isize, isize) {
+        let (x, y) = (origin.0 as isize, origin.1 as isize);
+        let depth = depth as isize;
+
+        match self {
+            Quadrant::North => (x + col, y - depth),
+            Quadrant::South => (x + col, y + depth),
+            Quadrant::East => (x + depth, y + col),
+            Quadrant::West => (x - depth, y + col),
+        }
+    }
+}
+
+// a fraction, used to avoid rounding errors of floats
+#[derive(Clone, Copy)]
+struct Slope {
+    num: isize,
+    den: isize,
+}
+
+impl Slope {
+    fn new(num: isize, den: isize) -> Slope {
+        Slope {num, den}
+    }
+
+    // the slope to the left edge of a tile
+    fn of_tile(depth: usize, col: isize) -> Slope {
+        Slope::new(2 * col - 1, 2 * depth as isize)
+    }
+}
+
+struct Row {
+    depth: usize,
+    start: Slope,
+    end: Slope,
+}
+
+impl Row {
+    // depth * start rounded, ties are rounded up
+    fn min_col(&self) -> isize {
+        let depth = self.depth as isize;
+        (2 * depth * self.start.num + self.start.den).div_euclid(2 * self.start.den)
+    }
+
+    // depth * end rounded, ties are rounded down
+    fn max_col(&self) -> isize {
+        let depth = self.depth as isize;
+        -(self.end.den - 2 * depth * self.end.num).div_euclid(2 * self.end.den)
+    }
+
+    // the center of the tile is between the start and end slope
+    fn symmetric(&self, col: isize) -> bool {
+        let depth = self.depth as isize;
+        let after_start = col * self.start.den >= depth * self.start.num;
+        let before_end = col * self.end.den <= depth * self.end.num;
+        after_start && before_end
+    }
+
+    fn next(&self) -> Row {
+        Row {depth: self.depth + 1, start: self.start, end: self.end}
+    }
+}
+
+// tiles outside of the map block the view
+fn is_opaque(map: &dyn Sight, tile: (isize, isize)) -> bool {
+    match in_bounds(map, tile) {
+        Some((x, y)) => map.opaque(x, y),
+        None => true,
+    }
+}
+
+fn in_bounds(map: &dyn Sight, tile: (isize, isize)) -> Option<(usize, usize)> {
+    if tile.0 < 0 || tile.1 < 0 {
+        return None;
+    }
+
+    let (x, y) = (tile.0 as usize, tile.1 as usize);
+    if x < map.width() && y < map.height() {
+        Some((x, y))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a room with walls around it and a pillar
+    struct Room {}
+
+    impl Sight for Room {
+        fn width(&self) -> usize {
+            11
+        }
+
+        fn height(&self) -> usize {
+            11
+        }
+
+        fn opaque(&self, x: usize, y: usize) -> bool {
+            if x == 0 || y == 0 || x == 10 || y == 10 {
+                return true;
+            }
+
+            x == 7 && y == 5
+        }
+    }
+
+    #[test]
+    fn test_field_of_view() {
+        let visible = field_of_view(&Room {}, (5, 5), 20);
+
+        // the walls of the room are seen
+        assert!(visible.contains(&(0, 5)));
+        assert!(visible.contains(&(5, 0)));
+        assert!(visible.contains(&(7, 5)));
+
+        // the tile behind the pillar is not
+        assert!(!visible.contains(&(8, 5)));
+        assert!(!visible.contains(&(9, 5)));
+        assert!(visible.contains(&(9, 1)));
+
+        // the radius limits the view
+        let visible = field_of_view(&Room {}, (5, 5), 2);
+        assert!(visible.contains(&(5, 3)));
+        assert!(!visible.contains(&(5, 2)));
+        assert!(!visible.contains(&(7, 7)));
+    }
+
+    #[test]
+    fn test_symmetry() {
+        let room = Room {};
+
+        for y in 1..10 {
+            for x in 1..10 {
+                if room.opaque(x, y) {
+                    continue;
+                }
+
+                for other in field_of_view(&room, (x, y), 20) {
+                    if room.opaque(other.0, other.1) {
+                        continue;
+                    }
+
+                    assert!(field_of_view(&room, other, 20).contains(&(x, y)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_line_of_sight() {
+        assert_eq!(vec![(0, 0), (1, 1), (2, 1), (3, 2)], line((0, 0), (3, 2)));
+        assert!(line_of_sight(&Room {}, (5, 5), (9, 9)));
+        assert!(!line_of_sight(&Room {}, (5, 5), (9, 5)));
+        assert!(line_of_sight(&Room {}, (5, 5), (7, 5)));
+    }
+
+    #[test]
+    fn test_fog_of_war() {
+        let mut fog = FogOfWar::new(11, 11);
+        assert_eq!(Visibility::Hidden, fog.get(5, 5));
+
+        fog.update(&Room {}, (2, 2), 2);
+        assert_eq!(Visibility::Visible, fog.get(3, 3));
+
+        fog.update(&Room {}, (8, 8), 2);
+        assert_eq!(Visibility::Explored, fog.get(3, 3));
+        assert_eq!(Visibility::Visible, fog.get(8, 9));
+        assert_eq!(Visibility::Hidden, fog.get(5, 5));
+
+        fog.reset();
+        assert_eq!(Visibility::Hidden, fog.get(3, 3));
+    }
+}
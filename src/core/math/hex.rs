@@ -0,0 +1,419 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+/*
+Hexagonal grids.
+
+a hex is stored in axial coordinates (q, r),
+the third cube coordinate s is always -q - r.
+most of the math follows the guide of red blob games
+*/
+
+// the 6 neighbours in axial coordinates, starting east and going counter clockwise
+const DIRECTIONS: [(i32, i32); 6] = [(1, 0), (1, -1), (0, -1), (-1, 0), (-1, 1), (0, 1)];
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Hex {
+    pub q: i32,
+    pub r: i32,
+}
+
+/*
+the offset coordinates (col, row) used to store a hex map in a 2D array.
+with Odd the odd rows (pointy) or columns (flat) are shoved by half a hex,
+with Even the even ones
+*/
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Offset {
+    OddRow,
+    EvenRow,
+    OddColumn,
+    EvenColumn,
+}
+
+impl Hex {
+    pub fn new(q: i32, r: i32) -> Hex {
+        Hex {q, r}
+    }
+
+    // create a hex from cube coordinates, q + r + s has to be 0
+    pub fn from_cube(q: i32, r: i32, s: i32) -> Result<Hex, String> {
+        if q + r + s != 0 {
+            return Err(format!("The cube coordinates ({}, {}, {}) do not add up to 0.", q, r, s));
+        }
+
+        Ok(Hex {q, r})
+    }
+
+    // returns the cube coordinates (q, r, s)
+    pub fn cube(&self) -> (i32, i32, i32) {
+        (self.q, self.r, self.s())
+    }
+
+    pub fn s(&self) -> i32 {
+        -self.q - self.r
+    }
+
+    // convert offset coordinates to a hex
+    pub fn from_offset(col: i32, row: i32, offset: Offset) -> Hex {
+        match offset {
+            Offset::OddRow => Hex::new(col - (row - (row & 1)) / 2, row),
+            Offset::EvenRow => Hex::new(col - (row + (row & 1)) / 2, row),
+            Offset::OddColumn => Hex::new(col, row - (col - (col & 1)) / 2),
+            Offset::EvenColumn => Hex::new(col, row - (col + (col & 1)) / 2),
+        }
+    }
+
+    // returns the offset coordinates (col, row)
+    pub fn to_offset(&self, offset: Offset) -> (i32, i32) {
+        let (q, r) = (self.q, self.r);
+
+        match offset {
+            Offset::OddRow => (q + (r - (r & 1)) / 2, r),
+            Offset::EvenRow => (q + (r + (r & 1)) / 2, r),
+            Offset::OddColumn => (q, r + (q - (q & 1)) / 2),
+            Offset::EvenColumn => (q, r + (q + (q & 1)) / 2),
+        }
+    }
+
+    // round fractional axial coordinates to the nearest hex
+    pub fn round(q: f32, r: f32) -> Hex {
+        let s = -q - r;
+        let (mut rq, mut rr, rs) = (q.round(), r.round(), s.round());
+        let (dq, dr, ds) = ((rq - q).abs(), (rr - r).abs(), (rs - s).abs());
+
+        // the coordinate with the largest rounding error is recalculated
+        if dq > dr && dq > ds {
+            rq = -rr - rs;
+        } else if dr > ds {
+            rr = -rq - rs;
+        }
+
+        Hex::new(rq as i32, rr as i32)
+    }
+
+    // the number of steps between two hexes
+    pub fn distance(&self, other: Hex) -> i32 {
+        let (dq, dr, ds) = (self.q - other.q, self.r - other.r, self.s() - other.s());
+        (dq.abs() + dr.abs() + ds.abs()) / 2
+    }
+
+    // the neighbour in direction 0 to 5, starting east and going counter clockwise
+    pub fn neighbour(&self, direction: usize) -> Hex {
+        let (dq, dr) = DIRECTIONS[direction % 6];
+        Hex::new(self.q + dq, self.r + dr)
+    }
+
+    // the 6 neighbours
+    pub fn neighbours(&self) -> [Hex; 6] {
+        let mut neighbours = [*self; 6];
+        for (i, neighbour) in neighbours.iter_mut().enumerate() {
+            *neighbour = self.neighbour(i);
+        }
+        neighbours
+    }
+
+    // all hexes with exactly the distance radius
+    pub fn ring(&self, radius: i32) -> Vec<Hex> {
+        if radius <= 0 {
+            return vec![*self];
+        }
+
+        // start at the hex radius steps in direction 4
+        // and walk radius steps along each side
+        let mut hex = Hex::new(self.q + DIRECTIONS[4].0 * radius, self.r + DIRECTIONS[4].1 * radius);
+        let mut ring = Vec::with_capacity(6 * radius as usize);
+
+        for direction in 0..6 {
+            for _ in 0..radius {
+                ring.push(hex);
+                hex = hex.neighbour(direction);
+            }
+        }
+
+        ring
+    }
+
+    // all hexes within radius ordered ring by ring, starting with this hex
+    pub fn spiral(&self, radius: i32) -> Vec<Hex> {
+        (0..=radius.max(0)).flat_map(|r| self.ring(r)).collect()
+    }
+
+    // the hexes of a straight line to another hex, both ends included
+    pub fn line(&self, other: Hex) -> Vec<Hex> {
+        let steps = self.distance(other);
+
+        // nudge the line so it never runs exactly between two hexes
+        let (aq, ar) = (self.q as f32 + 1e-6, self.r as f32 + 1e-6);
+        let (bq, br) = (other.q as f32 + 1e-6, other.r as f32 + 1e-6);
+
+        (0..=steps)
+            .map(|i| {
+                let t = if steps == 0 { 0.0 } else { i as f32 / steps as f32 };
+                Hex::round(aq + (bq - aq) * t, ar + (br - ar) * t)
+            })
+            .collect()
+    }
+}
+
+// pointy hexes have a corner at the top, flat hexes a flat side
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Orientation {
+    Pointy,
+    Flat,
+}
+
+impl Orientation {
+    // the matrix from axial to pixel and its inverse
+    fn matrix(&self) -> ([f32; 4], [f32; 4]) {
+        let sqrt3 = 3.0_f32.sqrt();
+
+        match self {
+            Orientation::Pointy => (
+                [sqrt3, sqrt3 / 2.0, 0.0, 1.5],
+                [sqrt3 / 3.0, -1.0 / 3.0, 0.0, 2.0 / 3.0],
+            ),
+            Orientation::Flat => (
+                [1.5, 0.0, sqrt3 / 2.0, sqrt3],
+                [2.0 / 3.0, 0.0, -1.0 / 3.0, sqrt3 / 3.0],
+            ),
+        }
+    }
+
+    // the angle of the first corner in degrees
+    fn start_angle(&self) -> f32 {
+        match self {
+            Orientation::Pointy => 30.0,
+            Orientation::Flat => 0.0,
+        }
+    }
+}
+
+/*
+converts between hexes and pixels
+
+size is the distance from the center to a corner in x and y direction,
+origin is the pixel position of the hex (0, 0).
+r grows in the y direction, use a negative size.1 to flip the map
+*/
+#[derive(Clone, Copy, Debug)]
+pub struct Layout {
+    pub orientation: Orientation,
+    pub size: (f32, f32),
+    pub origin: (f32, f32),
+}
+
+impl Layout {
+    pub fn new(orientation: Orientation, size: (f32, f32), origin: (f32, f32)) -> Layout {
+        Layout {orientation, size, origin}
+    }
+
+    // the pixel position of the center of a hex
+    pub fn to_pixel(&self, hex: Hex) -> (f32, f32) {
+        let (m, _) = self.orientation.matrix();
+        let (q, r) = (hex.q as f32, hex.r as f32);
+        let x = (m[0] * q + m[1] * r) * self.size.0;
+        let y = (m[2] * q + m[3] * r) * self.size.1;
+        (x + self.origin.0, y + self.origin.1)
+    }
+
+    // the hex that contains a pixel position, e.g. the mouse
+    pub fn from_pixel(&self, pixel: (f32, f32)) -> Hex {
+        let (_, m) = self.orientation.matrix();
+        let x = (pixel.0 - self.origin.0) / self.size.0;
+        let y = (pixel.1 - self.origin.1) / self.size.1;
+        Hex::round(m[0] * x + m[1] * y, m[2] * x + m[3] * y)
+    }
+
+    // the 6 corners of a hex in pixels
+    pub fn corners(&self, hex: Hex) -> [(f32, f32); 6] {
+        let (x, y) = self.to_pixel(hex);
+        let mut corners = [(0.0, 0.0); 6];
+
+        for (i, corner) in corners.iter_mut().enumerate() {
+            let angle = (self.orientation.start_angle() + 60.0 * i as f32).to_radians();
+            *corner = (x + self.size.0 * angle.cos(), y + self.size.1 * angle.sin());
+        }
+
+        corners
+    }
+
+    /*
+    the offset for a sprite with the dimension dim
+    so it is centered on the hex, a texture is drawn
+    from its bottom left corner
+    */
+    pub fn sprite_offset(&self, hex: Hex, dim: (f32, f32)) -> (f32, f32) {
+        let (x, y) = self.to_pixel(hex);
+        (x - dim.0 / 2.0, y - dim.1 / 2.0)
+    }
+}
+
+/**
+Implement HexMap for an Object,
+so you can use the A star pathfinding algorithm on hexes.
+*/
+pub trait HexMap {
+    /// can the hex be walked on, has to be false outside of the map
+    fn passable(&self, hex: Hex) -> bool;
+
+    /// the cost to step from a hex to its neighbour
+    fn cost(&self, _from: Hex, _to: Hex) -> u32 {
+        1
+    }
+}
+
+/**
+The A star algorithm on a hex map.
+
+returns the hexes from start to end, both included,
+or None if end can not be reached
+*/
+pub fn astar(map: &dyn HexMap, start: Hex, end: Hex) -> Option<Vec<Hex>> {
+    if !map.passable(start) || !map.passable(end) {
+        return None;
+    }
+
+    let mut open = BinaryHeap::new();
+    let mut costs: HashMap<Hex, u32> = HashMap::new();
+    let mut parents: HashMap<Hex, Hex> = HashMap::new();
+
+    // the hexes are ordered by their cube coordinates
+    // so equal f values are popped the same way every time
+    open.push(Reverse((start.distance(end) as u32, start.cube())));
+    costs.insert(start, 0);
+
+    while let Some(Reverse((_, (q, r, _)))) = open.pop() {
+        let current = Hex::new(q, r);
+
+        if current == end {
+            let mut path = vec![current];
+            let mut hex = current;
+
+            while let Some(parent) = parents.get(&hex) {
+                path.push(*parent);
+                hex = *parent;
+            }
+
+            path.reverse();
+            return Some(path);
+        }
+
+        let cost = costs[&current];
+
+        for neighbour in current.neighbours() {
+            if !map.passable(neighbour) {
+                continue;
+            }
+
+            let next = cost + map.cost(current, neighbour);
+            if costs.get(&neighbour).is_none_or(|known| next < *known) {
+                costs.insert(neighbour, next);
+                parents.insert(neighbour, current);
+                open.push(Reverse((next + neighbour.distance(end) as u32, neighbour.cube())));
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a hexagon shaped map with a wall of radius 2 around the center
+    // that is open in direction 0
+    struct Map {}
+
+    impl HexMap for Map {
+        fn passable(&self, hex: Hex) -> bool {
+            let center = Hex::new(0, 0);
+            let distance = hex.distance(center);
+            distance <= 4 && (distance != 2 || hex == Hex::new(2, 0))
+        }
+    }
+
+    #[test]
+    fn test_coordinates() {
+        let hex = Hex::new(3, -5);
+        assert_eq!((3, -5, 2), hex.cube());
+        assert!(Hex::from_cube(1, 1, 1).is_err());
+        assert_eq!(hex, Hex::from_cube(3, -5, 2).unwrap());
+
+        for offset in [Offset::OddRow, Offset::EvenRow, Offset::OddColumn, Offset::EvenColumn] {
+            for q in -3..3 {
+                for r in -3..3 {
+                    let (col, row) = Hex::new(q, r).to_offset(offset);
+                    assert_eq!(Hex::new(q, r), Hex::from_offset(col, row, offset));
+                }
+            }
+        }
+
+        assert_eq!((1, 1), Hex::new(1, 1).to_offset(Offset::OddRow));
+        assert_eq!((2, 1), Hex::new(1, 1).to_offset(Offset::EvenRow));
+    }
+
+    #[test]
+    fn test_distance_ring_line() {
+        let center = Hex::new(0, 0);
+        assert_eq!(3, center.distance(Hex::new(3, -3)));
+        assert_eq!(4, Hex::new(-2, 1).distance(Hex::new(2, -1)));
+
+        assert_eq!(vec![center], center.ring(0));
+        for radius in 1..5 {
+            let ring = center.ring(radius);
+            assert_eq!(6 * radius as usize, ring.len());
+            assert!(ring.iter().all(|hex| hex.distance(center) == radius));
+        }
+        assert_eq!(1 + 6 + 12 + 18, center.spiral(3).len());
+
+        let line = center.line(Hex::new(4, -2));
+        assert_eq!(5, line.len());
+        for pair in line.windows(2) {
+            assert_eq!(1, pair[0].distance(pair[1]));
+        }
+    }
+
+    #[test]
+    fn test_layout() {
+        for orientation in [Orientation::Pointy, Orientation::Flat] {
+            let layout = Layout::new(orientation, (32.0, 32.0), (400.0, 300.0));
+            assert_eq!((400.0, 300.0), layout.to_pixel(Hex::new(0, 0)));
+
+            for hex in Hex::new(0, 0).spiral(3) {
+                let (x, y) = layout.to_pixel(hex);
+                assert_eq!(hex, layout.from_pixel((x, y)));
+                // a point close to the center is still inside
+                assert_eq!(hex, layout.from_pixel((x + 10.0, y - 10.0)));
+
+                // all corners have the same distance to the center
+                for (cx, cy) in layout.corners(hex) {
+                    let distance = ((cx - x).powi(2) + (cy - y).powi(2)).sqrt();
+                    assert!((distance - 32.0).abs() < 0.001);
+                }
+            }
+        }
+
+        let layout = Layout::new(Orientation::Pointy, (10.0, 10.0), (0.0, 0.0));
+        assert_eq!((-8.0, -8.0), layout.sprite_offset(Hex::new(0, 0), (16.0, 16.0)));
+    }
+
+    #[test]
+    fn test_astar() {
+        let map = Map {};
+        let path = astar(&map, Hex::new(0, 0), Hex::new(-3, 0)).unwrap();
+
+        // the way around the wall through the gap
+        assert_eq!(Hex::new(0, 0), path[0]);
+        assert_eq!(Hex::new(-3, 0), path[path.len() - 1]);
+        assert!(path.contains(&Hex::new(2, 0)));
+        assert!(path.iter().all(|hex| map.passable(*hex)));
+        for pair in path.windows(2) {
+            assert_eq!(1, pair[0].distance(pair[1]));
+        }
+
+        assert!(astar(&map, Hex::new(0, 0), Hex::new(5, 0)).is_none());
+        assert_eq!(1, astar(&map, Hex::new(1, 0), Hex::new(1, 0)).unwrap().len());
+    }
+}
@@ -0,0 +1,199 @@
+use nalgebra_glm::vec4;
+use crate::core::math::transform::Transform;
+use crate::core::mouse::Mouse;
+
+/*
+Isometric projection.
+
+the tiles are diamonds with the size tile_width x tile_height in pixels,
+the tile x axis goes to the bottom right and the y axis to the bottom left.
+the point x, y in tile space is the top corner of the tile x, y
+so the center of a tile is at x + 0.5, y + 0.5.
+origin is the screen position of the top corner of the tile 0, 0
+*/
+#[derive(Clone, Copy, Debug)]
+pub struct Projection {
+    pub tile_width: f32,
+    pub tile_height: f32,
+    pub origin: (f32, f32),
+}
+
+impl Projection {
+    // a projection with any ratio between tile width and height
+    pub fn dimetric(tile_width: f32, tile_height: f32, origin: (f32, f32)) -> Projection {
+        Projection {tile_width, tile_height, origin}
+    }
+
+    // the 2:1 projection of most pixel art games
+    pub fn isometric(tile_width: f32, origin: (f32, f32)) -> Projection {
+        Projection::dimetric(tile_width, tile_width / 2.0, origin)
+    }
+
+    // the mathematically correct projection with 120 degrees between the axes
+    pub fn true_isometric(tile_width: f32, origin: (f32, f32)) -> Projection {
+        Projection::dimetric(tile_width, tile_width * 30.0_f32.to_radians().tan(), origin)
+    }
+
+    // convert a point in tile space to the screen
+    pub fn to_screen(&self, x: f32, y: f32) -> (f32, f32) {
+        (
+            (x - y) * self.tile_width / 2.0 + self.origin.0,
+            (x + y) * self.tile_height / 2.0 + self.origin.1,
+        )
+    }
+
+    // convert a point on the screen to tile space
+    pub fn to_tile(&self, screen_x: f32, screen_y: f32) -> (f32, f32) {
+        let sx = (screen_x - self.origin.0) / (self.tile_width / 2.0);
+        let sy = (screen_y - self.origin.1) / (self.tile_height / 2.0);
+        ((sy + sx) / 2.0, (sy - sx) / 2.0)
+    }
+
+    // the tile under a point on the screen, None if it is left or above tile 0, 0
+    pub fn tile_at(&self, screen_x: f32, screen_y: f32) -> Option<(usize, usize)> {
+        let (x, y) = self.to_tile(screen_x, screen_y);
+
+        if x < 0.0 || y < 0.0 {
+            return None;
+        }
+
+        Some((x.floor() as usize, y.floor() as usize))
+    }
+
+    /*
+    the tile under the mouse
+    the camera is the same transform that is passed to draw,
+    so a moved or zoomed camera still picks the right tile
+    */
+    pub fn pick(&self, mouse: &Mouse, camera: &Transform) -> Option<(usize, usize)> {
+        let inverse = nalgebra_glm::inverse(&camera.matrix());
+        let world = inverse * vec4(mouse.x, mouse.y, 0.0, 1.0);
+        self.tile_at(world.x, world.y)
+    }
+
+    /*
+    the offset to draw a sprite with the dimension dim on a tile,
+    the bottom of the sprite is on the bottom corner of the tile
+    so higher sprites like walls or trees grow upwards
+    */
+    pub fn sprite_offset(&self, x: f32, y: f32, dim: (f32, f32)) -> (f32, f32) {
+        let (center_x, _) = self.to_screen(x + 0.5, y + 0.5);
+        let (_, bottom_y) = self.to_screen(x + 1.0, y + 1.0);
+        (center_x - dim.0 / 2.0, bottom_y - dim.1)
+    }
+}
+
+/*
+collects tiles and sprites and returns them back to front
+
+things further back (smaller x + y) are drawn first,
+on the same position the lower layer is drawn first
+and with the same depth the order they were pushed in is kept.
+push everything of a frame, then add the sorted items
+to an InstancedShader, instances are drawn in the order they were added
+*/
+pub struct DepthQueue<T> {
+    items: Vec<((f32, f32, usize), T)>,
+}
+
+impl<T> DepthQueue<T> {
+    pub fn new() -> DepthQueue<T> {
+        DepthQueue {items: vec![]}
+    }
+
+    /*
+    add an item at the position x, y in tile space
+    for a sprite use the tile position of its feet,
+    layer is e.g. 0 for the floor and 1 for the objects on it
+    */
+    pub fn push(&mut self, x: f32, y: f32, layer: f32, item: T) {
+        let order = self.items.len();
+        self.items.push(((x + y, layer, order), item));
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    // returns the items back to front and empties the queue
+    pub fn drain(&mut self) -> Vec<T> {
+        self.items.sort_by(|(a, _), (b, _)| {
+            a.0.total_cmp(&b.0)
+                .then(a.1.total_cmp(&b.1))
+                .then(a.2.cmp(&b.2))
+        });
+
+        self.items.drain(..).map(|(_, item)| item).collect()
+    }
+}
+
+impl<T> Default for DepthQueue<T> {
+    fn default() -> Self {
+        DepthQueue::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::mouse::MouseWheelState;
+
+    #[test]
+    fn test_projection() {
+        let iso = Projection::isometric(64.0, (400.0, 100.0));
+        assert_eq!((400.0, 100.0), iso.to_screen(0.0, 0.0));
+        assert_eq!((432.0, 116.0), iso.to_screen(1.0, 0.0));
+        assert_eq!((368.0, 116.0), iso.to_screen(0.0, 1.0));
+
+        for projection in [iso, Projection::true_isometric(64.0, (10.0, 20.0)), Projection::dimetric(48.0, 16.0, (0.0, 0.0))] {
+            for (x, y) in [(0.0, 0.0), (3.5, 1.25), (-2.0, 7.0)] {
+                let (sx, sy) = projection.to_screen(x, y);
+                let (tx, ty) = projection.to_tile(sx, sy);
+                assert!((tx - x).abs() < 0.0001 && (ty - y).abs() < 0.0001);
+            }
+        }
+
+        // the center of the tile 2, 3
+        let (sx, sy) = iso.to_screen(2.5, 3.5);
+        assert_eq!(Some((2, 3)), iso.tile_at(sx, sy));
+        assert_eq!(None, iso.tile_at(0.0, 0.0));
+
+        assert_eq!((384.0, 68.0), iso.sprite_offset(0.0, 0.0, (32.0, 64.0)));
+    }
+
+    #[test]
+    fn test_pick() {
+        let iso = Projection::isometric(64.0, (400.0, 100.0));
+        let (sx, sy) = iso.to_screen(4.5, 1.5);
+
+        // the camera moves the world 100 pixels to the left
+        let mut camera = Transform::new();
+        camera.set(-100.0, 0.0, 0.0);
+
+        let mouse = Mouse::new(sx - 100.0, sy, false, false, MouseWheelState::None);
+        assert_eq!(Some((4, 1)), iso.pick(&mouse, &camera));
+
+        let mouse = Mouse::new(sx, sy, false, false, MouseWheelState::None);
+        assert_eq!(Some((4, 1)), iso.pick(&mouse, &Transform::new()));
+    }
+
+    #[test]
+    fn test_depth_queue() {
+        let mut queue = DepthQueue::new();
+        queue.push(2.0, 2.0, 0.0, "floor 2 2");
+        queue.push(1.5, 1.0, 1.0, "player");
+        queue.push(0.0, 0.0, 0.0, "floor 0 0");
+        queue.push(2.0, 2.0, 1.0, "tree");
+        queue.push(1.0, 1.0, 0.0, "floor 1 1");
+        queue.push(1.0, 1.0, 0.0, "decal 1 1");
+        assert_eq!(6, queue.len());
+
+        let sorted = queue.drain();
+        assert_eq!(vec!["floor 0 0", "floor 1 1", "decal 1 1", "player", "floor 2 2", "tree"], sorted);
+        assert!(queue.is_empty());
+    }
+}
@@ -0,0 +1,422 @@
+use crate::core::seed::Seed;
+use crate::core::resource::image::Image;
+use crate::core::math::linear;
+
+/*
+coherent noise for terrain, clouds or wobble effects
+
+the permutation table is shuffled with a seed, so the same
+seed always creates the same noise. gradient, simplex and value
+noise return values from -1.0 to 1.0, worley noise returns distances
+*/
+pub struct Noise {
+    perm: Vec<usize>,
+}
+
+// the 12 gradients in the middle of the edges of a cube
+const GRADIENTS_3D: [(f32, f32, f32); 12] = [
+    (1.0, 1.0, 0.0), (-1.0, 1.0, 0.0), (1.0, -1.0, 0.0), (-1.0, -1.0, 0.0),
+    (1.0, 0.0, 1.0), (-1.0, 0.0, 1.0), (1.0, 0.0, -1.0), (-1.0, 0.0, -1.0),
+    (0.0, 1.0, 1.0), (0.0, -1.0, 1.0), (0.0, 1.0, -1.0), (0.0, -1.0, -1.0),
+];
+
+// 8 gradients around a circle
+const GRADIENTS_2D: [(f32, f32); 8] = [
+    (1.0, 0.0), (-1.0, 0.0), (0.0, 1.0), (0.0, -1.0),
+    (0.70710677, 0.70710677), (-0.70710677, 0.70710677),
+    (0.70710677, -0.70710677), (-0.70710677, -0.70710677),
+];
+
+impl Noise {
+    // create the noise from a seed
+    pub fn new(seed: &mut Seed) -> Noise {
+        let mut table: Vec<usize> = (0..256).collect();
+        seed.shuffle(&mut table);
+
+        // the table is doubled so lookups never wrap
+        let mut perm = table.clone();
+        perm.extend_from_slice(&table);
+        Noise {perm}
+    }
+
+    // 1D gradient noise
+    pub fn perlin1(&self, x: f32) -> f32 {
+        let xi = x.floor();
+        let xf = x - xi;
+        let xi = wrap(xi);
+
+        let g0 = self.gradient1(self.perm[xi], xf);
+        let g1 = self.gradient1(self.perm[xi + 1], xf - 1.0);
+
+        // the largest gradient is 8.0 so scale to -1.0 to 1.0
+        linear::lerp(g0, g1, fade(xf)) * 0.25
+    }
+
+    // 2D gradient noise
+    pub fn perlin2(&self, x: f32, y: f32) -> f32 {
+        let (xi, yi) = (x.floor(), y.floor());
+        let (xf, yf) = (x - xi, y - yi);
+        let (xi, yi) = (wrap(xi), wrap(yi));
+
+        let corner = |cx: usize, cy: usize, dx: f32, dy: f32| {
+            let hash = self.perm[self.perm[xi + cx] + yi + cy];
+            let (gx, gy) = GRADIENTS_2D[hash % 8];
+            gx * dx + gy * dy
+        };
+
+        let (u, v) = (fade(xf), fade(yf));
+        let bottom = linear::lerp(corner(0, 0, xf, yf), corner(1, 0, xf - 1.0, yf), u);
+        let top = linear::lerp(corner(0, 1, xf, yf - 1.0), corner(1, 1, xf - 1.0, yf - 1.0), u);
+
+        // the maximum of 2D gradient noise is sqrt(0.5)
+        (linear::lerp(bottom, top, v) * std::f32::consts::SQRT_2).clamp(-1.0, 1.0)
+    }
+
+    // 3D gradient noise (improved perlin noise)
+    pub fn perlin3(&self, x: f32, y: f32, z: f32) -> f32 {
+        let (xi, yi, zi) = (x.floor(), y.floor(), z.floor());
+        let (xf, yf, zf) = (x - xi, y - yi, z - zi);
+        let (xi, yi, zi) = (wrap(xi), wrap(yi), wrap(zi));
+
+        let corner = |cx: usize, cy: usize, cz: usize| {
+            let hash = self.perm[self.perm[self.perm[xi + cx] + yi + cy] + zi + cz];
+            let (gx, gy, gz) = GRADIENTS_3D[hash % 12];
+            gx * (xf - cx as f32) + gy * (yf - cy as f32) + gz * (zf - cz as f32)
+        };
+
+        let (u, v, w) = (fade(xf), fade(yf), fade(zf));
+        let x00 = linear::lerp(corner(0, 0, 0), corner(1, 0, 0), u);
+        let x10 = linear::lerp(corner(0, 1, 0), corner(1, 1, 0), u);
+        let x01 = linear::lerp(corner(0, 0, 1), corner(1, 0, 1), u);
+        let x11 = linear::lerp(corner(0, 1, 1), corner(1, 1, 1), u);
+        let y0 = linear::lerp(x00, x10, v);
+        let y1 = linear::lerp(x01, x11, v);
+
+        linear::lerp(y0, y1, w).clamp(-1.0, 1.0)
+    }
+
+    // 2D simplex noise, less directional artifacts than perlin noise
+    pub fn simplex2(&self, x: f32, y: f32) -> f32 {
+        const F2: f32 = 0.3660254; // (sqrt(3) - 1) / 2
+        const G2: f32 = 0.21132487; // (3 - sqrt(3)) / 6
+
+        // skew the input space to find the simplex cell
+        let s = (x + y) * F2;
+        let (i, j) = ((x + s).floor(), (y + s).floor());
+        let t = (i + j) * G2;
+        let (x0, y0) = (x - (i - t), y - (j - t));
+
+        // the second corner is either right or above
+        let (i1, j1) = if x0 > y0 { (1, 0) } else { (0, 1) };
+        let (x1, y1) = (x0 - i1 as f32 + G2, y0 - j1 as f32 + G2);
+        let (x2, y2) = (x0 - 1.0 + 2.0 * G2, y0 - 1.0 + 2.0 * G2);
+
+        let (ii, jj) = (wrap(i), wrap(j));
+        let contribution = |ci: usize, cj: usize, dx: f32, dy: f32| {
+            let t = 0.5 - dx * dx - dy * dy;
+            if t < 0.0 {
+                return 0.0;
+            }
+
+            let hash = self.perm[ii + ci + self.perm[jj + cj]];
+            let (gx, gy, _) = GRADIENTS_3D[hash % 12];
+            t.powi(4) * (gx * dx + gy * dy)
+        };
+
+        let n = contribution(0, 0, x0, y0) + contribution(i1, j1, x1, y1) + contribution(1, 1, x2, y2);
+        (70.0 * n).clamp(-1.0, 1.0)
+    }
+
+    // 3D simplex noise
+    pub fn simplex3(&self, x: f32, y: f32, z: f32) -> f32 {
+        const F3: f32 = 1.0 / 3.0;
+        const G3: f32 = 1.0 / 6.0;
+
+        let s = (x + y + z) * F3;
+        let (i, j, k) = ((x + s).floor(), (y + s).floor(), (z + s).floor());
+        let t = (i + j + k) * G3;
+        let (x0, y0, z0) = (x - (i - t), y - (j - t), z - (k - t));
+
+        // find out in which of the 6 tetrahedrons we are
+        let (o1, o2) = if x0 >= y0 {
+            if y0 >= z0 {
+                ((1, 0, 0), (1, 1, 0))
+            } else if x0 >= z0 {
+                ((1, 0, 0), (1, 0, 1))
+            } else {
+                ((0, 0, 1), (1, 0, 1))
+            }
+        } else if y0 < z0 {
+            ((0, 0, 1), (0, 1, 1))
+        } else if x0 < z0 {
+            ((0, 1, 0), (0, 1, 1))
+        } else {
+            ((0, 1, 0), (1, 1, 0))
+        };
+
+        let (ii, jj, kk) = (wrap(i), wrap(j), wrap(k));
+        let contribution = |c: (usize, usize, usize), g: f32| {
+            let dx = x0 - c.0 as f32 + g;
+            let dy = y0 - c.1 as f32 + g;
+            let dz = z0 - c.2 as f32 + g;
+            let t = 0.6 - dx * dx - dy * dy - dz * dz;
+            if t < 0.0 {
+                return 0.0;
+            }
+
+            let hash = self.perm[ii + c.0 + self.perm[jj + c.1 + self.perm[kk + c.2]]];
+            let (gx, gy, gz) = GRADIENTS_3D[hash % 12];
+            t.powi(4) * (gx * dx + gy * dy + gz * dz)
+        };
+
+        let n = contribution((0, 0, 0), 0.0)
+            + contribution(o1, G3)
+            + contribution(o2, 2.0 * G3)
+            + contribution((1, 1, 1), 3.0 * G3);
+
+        (32.0 * n).clamp(-1.0, 1.0)
+    }
+
+    // 2D value noise, random values on the grid smoothly interpolated
+    pub fn value2(&self, x: f32, y: f32) -> f32 {
+        let (xi, yi) = (x.floor(), y.floor());
+        let (xf, yf) = (x - xi, y - yi);
+        let (xi, yi) = (wrap(xi), wrap(yi));
+
+        let value = |cx: usize, cy: usize| {
+            let hash = self.perm[self.perm[xi + cx] + yi + cy];
+            hash as f32 / 127.5 - 1.0
+        };
+
+        let (u, v) = (fade(xf), fade(yf));
+        let bottom = linear::lerp(value(0, 0), value(1, 0), u);
+        let top = linear::lerp(value(0, 1), value(1, 1), u);
+        linear::lerp(bottom, top, v)
+    }
+
+    /*
+    2D worley (cellular) noise
+    every grid cell holds one random feature point,
+    returns the distance to the nearest and the second nearest point
+    */
+    pub fn worley2(&self, x: f32, y: f32) -> (f32, f32) {
+        let (cell_x, cell_y) = (x.floor(), y.floor());
+        let mut nearest = (f32::MAX, f32::MAX);
+
+        for oy in -1..=1 {
+            for ox in -1..=1 {
+                let cx = cell_x + ox as f32;
+                let cy = cell_y + oy as f32;
+
+                // the position of the feature point inside its cell
+                let hash = self.perm[self.perm[wrap(cx)] + wrap(cy)];
+                let px = cx + self.perm[hash] as f32 / 256.0;
+                let py = cy + self.perm[hash + 1] as f32 / 256.0;
+
+                let dist = ((px - x).powi(2) + (py - y).powi(2)).sqrt();
+                if dist < nearest.0 {
+                    nearest = (dist, nearest.0);
+                } else if dist < nearest.1 {
+                    nearest.1 = dist;
+                }
+            }
+        }
+
+        nearest
+    }
+
+    fn gradient1(&self, hash: usize, x: f32) -> f32 {
+        let gradient = 1.0 + (hash & 7) as f32;
+        if hash & 8 == 0 {
+            gradient * x
+        } else {
+            -gradient * x
+        }
+    }
+}
+
+/*
+combines multiple octaves of a noise function
+every octave has a higher frequency (lacunarity)
+and a lower amplitude (gain) than the one before
+
+use it with any of the noise functions
+fractal.fbm(|x, y| noise.perlin2(x, y), x, y)
+*/
+#[derive(Clone, Copy)]
+pub struct Fractal {
+    pub octaves: usize,
+    pub lacunarity: f32,
+    pub gain: f32,
+}
+
+impl Fractal {
+    pub fn new(octaves: usize, lacunarity: f32, gain: f32) -> Fractal {
+        Fractal {octaves, lacunarity, gain}
+    }
+
+    // fractional brownian motion, the sum of the octaves from -1.0 to 1.0
+    pub fn fbm(&self, noise: impl Fn(f32, f32) -> f32, x: f32, y: f32) -> f32 {
+        self.sum(noise, x, y)
+    }
+
+    // sharp ridges like mountains from 0.0 to 1.0
+    pub fn ridged(&self, noise: impl Fn(f32, f32) -> f32, x: f32, y: f32) -> f32 {
+        let value = self.sum(|x, y| 1.0 - noise(x, y).abs(), x, y);
+        value.powi(2)
+    }
+
+    // the sum of the absolute octaves from 0.0 to 1.0, looks like fire or smoke
+    pub fn turbulence(&self, noise: impl Fn(f32, f32) -> f32, x: f32, y: f32) -> f32 {
+        self.sum(|x, y| noise(x, y).abs(), x, y)
+    }
+
+    /*
+    domain warping, the position is moved by the noise itself
+    before it is sampled, strength is the distance of the move
+    */
+    pub fn warp(&self, noise: impl Fn(f32, f32) -> f32, x: f32, y: f32, strength: f32) -> f32 {
+        // the offsets make the two directions independent
+        let wx = self.fbm(&noise, x, y);
+        let wy = self.fbm(&noise, x + 5.2, y + 1.3);
+        self.fbm(&noise, x + wx * strength, y + wy * strength)
+    }
+
+    // the normalized sum of all octaves
+    fn sum(&self, noise: impl Fn(f32, f32) -> f32, x: f32, y: f32) -> f32 {
+        let mut frequency = 1.0;
+        let mut amplitude = 1.0;
+        let mut value = 0.0;
+        let mut max = 0.0;
+
+        for _ in 0..self.octaves {
+            value += noise(x * frequency, y * frequency) * amplitude;
+            max += amplitude;
+            frequency *= self.lacunarity;
+            amplitude *= self.gain;
+        }
+
+        if max == 0.0 {
+            return 0.0;
+        }
+
+        value / max
+    }
+}
+
+impl Default for Fractal {
+    // 5 octaves, each with double the frequency and half the amplitude
+    fn default() -> Self {
+        Fractal::new(5, 2.0, 0.5)
+    }
+}
+
+/*
+bake a region of a noise function into a greyscale image
+x, y, width and height are the region in noise space,
+pixels is the size of the image.
+values from -1.0 to 1.0 go from black to white
+*/
+pub fn bake(noise: impl Fn(f32, f32) -> f32, region: (f32, f32, f32, f32), pixels: (u32, u32)) -> Image {
+    let (x, y, width, height) = region;
+    let (pixels_x, pixels_y) = pixels;
+
+    let image = image::RgbaImage::from_fn(pixels_x, pixels_y, |px, py| {
+        let nx = x + px as f32 / pixels_x as f32 * width;
+        // images are stored upside down for opengl
+        let ny = y + (pixels_y - 1 - py) as f32 / pixels_y as f32 * height;
+        let value = linear::lint(noise(nx, ny).clamp(-1.0, 1.0), -1.0, 1.0, 0.0, 255.0) as u8;
+        image::Rgba([value, value, value, 255])
+    });
+
+    Image::from(image)
+}
+
+// the curve 6t^5 - 15t^4 + 10t^3 for smooth interpolation
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+// the lattice coordinate inside the permutation table
+fn wrap(value: f32) -> usize {
+    (value as i64).rem_euclid(256) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // sample a noise function on a grid between integers
+    fn samples(noise: impl Fn(f32, f32) -> f32) -> Vec<f32> {
+        let mut values = vec![];
+        for y in 0..40 {
+            for x in 0..40 {
+                values.push(noise(x as f32 * 0.37 - 7.0, y as f32 * 0.41 - 7.0));
+            }
+        }
+        values
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let a = Noise::new(&mut Seed::from_str("noise"));
+        let b = Noise::new(&mut Seed::from_str("noise"));
+        let c = Noise::new(&mut Seed::from_str("other"));
+
+        assert_eq!(samples(|x, y| a.perlin2(x, y)), samples(|x, y| b.perlin2(x, y)));
+        assert_ne!(samples(|x, y| a.perlin2(x, y)), samples(|x, y| c.perlin2(x, y)));
+    }
+
+    #[test]
+    fn test_range() {
+        let noise = Noise::new(&mut Seed::from_str("range"));
+        let fractal = Fractal::default();
+
+        let in_range = |values: Vec<f32>, min: f32, max: f32| {
+            values.iter().all(|v| min <= *v && *v <= max) &&
+            values.iter().any(|v| *v != values[0])
+        };
+
+        assert!(in_range(samples(|x, _| noise.perlin1(x)), -1.0, 1.0));
+        assert!(in_range(samples(|x, y| noise.perlin2(x, y)), -1.0, 1.0));
+        assert!(in_range(samples(|x, y| noise.perlin3(x, y, 0.5)), -1.0, 1.0));
+        assert!(in_range(samples(|x, y| noise.simplex2(x, y)), -1.0, 1.0));
+        assert!(in_range(samples(|x, y| noise.simplex3(x, y, 0.5)), -1.0, 1.0));
+        assert!(in_range(samples(|x, y| noise.value2(x, y)), -1.0, 1.0));
+        assert!(in_range(samples(|x, y| noise.worley2(x, y).0), 0.0, 1.5));
+        assert!(in_range(samples(|x, y| fractal.fbm(|x, y| noise.perlin2(x, y), x, y)), -1.0, 1.0));
+        assert!(in_range(samples(|x, y| fractal.ridged(|x, y| noise.simplex2(x, y), x, y)), 0.0, 1.0));
+        assert!(in_range(samples(|x, y| fractal.turbulence(|x, y| noise.perlin2(x, y), x, y)), 0.0, 1.0));
+        assert!(in_range(samples(|x, y| fractal.warp(|x, y| noise.perlin2(x, y), x, y, 2.0)), -1.0, 1.0));
+    }
+
+    #[test]
+    fn test_gradient_noise() {
+        let noise = Noise::new(&mut Seed::from_str("gradient"));
+
+        // gradient noise is 0.0 on the grid
+        assert_eq!(0.0, noise.perlin1(3.0));
+        assert_eq!(0.0, noise.perlin2(-4.0, 12.0));
+        assert_eq!(0.0, noise.perlin3(1.0, 2.0, 3.0));
+
+        // and continuous between
+        let a = noise.perlin2(1.5, 2.5);
+        let b = noise.perlin2(1.501, 2.5);
+        assert!((a - b).abs() < 0.01);
+
+        let (f1, f2) = noise.worley2(0.5, 0.5);
+        assert!(f1 <= f2);
+    }
+
+    #[test]
+    fn test_bake() {
+        let noise = Noise::new(&mut Seed::from_str("bake"));
+        let image = bake(|x, y| noise.perlin2(x, y), (0.0, 0.0, 4.0, 2.0), (64, 32));
+        assert_eq!(64.0, image.width);
+        assert_eq!(32.0, image.height);
+
+        // the bottom left pixel is the noise origin, which is 0.0 (grey)
+        let data = image.to_rgba_image();
+        assert_eq!(127, data.get_pixel(0, 31)[0]);
+    }
+}
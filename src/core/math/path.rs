@@ -0,0 +1,358 @@
+use crate::core::math::pathfinding::Maze;
+use crate::core::math::transform::Transform;
+use crate::core::math::collision;
+
+/*
+Post processing for the paths returned by
+the A star algorithm.
+
+the raw path goes from cell to cell which
+results in zig-zag movement, these helpers
+remove unneeded cells, smooth the path into
+world space points and move a transform along it
+*/
+
+/// true if a straight line from the center of cell `from`
+/// to the center of cell `to` only touches walkable cells
+pub fn line_of_sight(maze: &dyn Maze, from: (usize, usize), to: (usize, usize)) -> bool {
+    let (mut x, mut y) = (from.0 as isize, from.1 as isize);
+    let dx = to.0 as isize - x;
+    let dy = to.1 as isize - y;
+    let (nx, ny) = (dx.abs(), dy.abs());
+    let step_x = dx.signum();
+    let step_y = dy.signum();
+
+    // supercover traversal, every cell the line
+    // passes through is visited, not only one per column
+    let (mut ix, mut iy) = (0, 0);
+
+    if blocked(maze, x, y) {
+        return false;
+    }
+
+    while ix < nx || iy < ny {
+        let decision = (1 + 2 * ix) * ny - (1 + 2 * iy) * nx;
+
+        if decision == 0 {
+            // the line goes exactly through a corner
+            // so both neighbours are touched as well
+            if blocked(maze, x + step_x, y) || blocked(maze, x, y + step_y) {
+                return false;
+            }
+
+            x += step_x;
+            y += step_y;
+            ix += 1;
+            iy += 1;
+        } else if decision < 0 {
+            x += step_x;
+            ix += 1;
+        } else {
+            y += step_y;
+            iy += 1;
+        }
+
+        if blocked(maze, x, y) {
+            return false;
+        }
+    }
+
+    true
+}
+
+/**
+remove all cells from a path that can be skipped
+by walking in a straight line (string pulling)
+
+the first and the last cell are always kept
+*/
+pub fn string_pull(maze: &dyn Maze, path: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    if path.len() < 3 {
+        return path.to_vec();
+    }
+
+    let mut pulled = vec![path[0]];
+    let mut anchor = 0;
+
+    while anchor < path.len() - 1 {
+        // search for the furthest cell we can still see
+        let mut next = anchor + 1;
+        for i in (anchor + 2..path.len()).rev() {
+            if line_of_sight(maze, path[anchor], path[i]) {
+                next = i;
+                break;
+            }
+        }
+
+        pulled.push(path[next]);
+        anchor = next;
+    }
+
+    pulled
+}
+
+/// convert a cell path into world space points
+/// at the center of each tile
+pub fn to_world(path: &[(usize, usize)], tile_size: f32) -> Vec<(f32, f32)> {
+    path.iter()
+        .map(|(x, y)| {
+            let x = *x as f32 * tile_size + tile_size / 2.0;
+            let y = *y as f32 * tile_size + tile_size / 2.0;
+            (x, y)
+        })
+        .collect()
+}
+
+/**
+smooth a cell path into world space points
+using a catmull rom spline through the tile centers
+
+segments is the number of points
+created between two cells of the path
+*/
+pub fn smooth(path: &[(usize, usize)], tile_size: f32, segments: usize) -> Vec<(f32, f32)> {
+    let points = to_world(path, tile_size);
+
+    if points.len() < 3 || segments == 0 {
+        return points;
+    }
+
+    let mut smoothed = vec![];
+    let last = points.len() - 1;
+
+    for i in 0..last {
+        // the outer points are duplicated
+        // so the spline starts and ends on the path
+        let p0 = points[if i == 0 { 0 } else { i - 1 }];
+        let p1 = points[i];
+        let p2 = points[i + 1];
+        let p3 = points[if i + 2 > last { last } else { i + 2 }];
+
+        for s in 0..segments {
+            let t = s as f32 / segments as f32;
+            smoothed.push(catmull_rom(p0, p1, p2, p3, t));
+        }
+    }
+
+    smoothed.push(points[last]);
+    smoothed
+}
+
+/// evaluate a catmull rom segment between p1 and p2 at t (0.0 to 1.0)
+pub fn catmull_rom(p0: (f32, f32), p1: (f32, f32), p2: (f32, f32), p3: (f32, f32), t: f32) -> (f32, f32) {
+    let t2 = t * t;
+    let t3 = t2 * t;
+
+    let calc = |a: f32, b: f32, c: f32, d: f32| {
+        0.5 * (
+            2.0 * b +
+            (-a + c) * t +
+            (2.0 * a - 5.0 * b + 4.0 * c - d) * t2 +
+            (-a + 3.0 * b - 3.0 * c + d) * t3
+        )
+    };
+
+    (calc(p0.0, p1.0, p2.0, p3.0), calc(p0.1, p1.1, p2.1, p3.1))
+}
+
+/**
+moves a transform along a path of world space points
+
+call update every frame with the delta time
+of the Performance struct
+*/
+pub struct PathFollower {
+    points: Vec<(f32, f32)>,
+    index: usize,
+    pub speed: f32,
+    pub tolerance: f32,
+    length: f32,
+    // the distance from the start of the transform to the first point,
+    // measured with the first update
+    lead_in: Option<f32>,
+    travelled: f32,
+}
+
+impl PathFollower {
+    // create a new PathFollower with a speed in units per second
+    // and the distance at which the end counts as reached
+    pub fn new(points: Vec<(f32, f32)>, speed: f32, tolerance: f32) -> PathFollower {
+        let length = points
+            .windows(2)
+            .map(|w| collision::distance(w[0].0, w[0].1, w[1].0, w[1].1))
+            .sum();
+
+        PathFollower {points, index: 0, speed, tolerance, length, lead_in: None, travelled: 0.0}
+    }
+
+    /*
+    move the transform towards the next points of the path
+    the z value of the transform is kept
+    returns true once the end of the path is reached
+    */
+    pub fn update(&mut self, transform: &mut Transform, delta: f32) -> bool {
+        if self.arrived() {
+            return true;
+        }
+
+        let (mut x, mut y, z) = transform.pos();
+        let mut remaining = self.speed * delta;
+
+        // the way to the first point counts for the progress as well
+        if self.lead_in.is_none() {
+            let (tx, ty) = self.points[0];
+            self.lead_in = Some(collision::distance(x, y, tx, ty));
+        }
+
+        while self.index < self.points.len() {
+            let (tx, ty) = self.points[self.index];
+            let dist = collision::distance(x, y, tx, ty);
+            let is_last = self.index == self.points.len() - 1;
+
+            if dist <= remaining || (is_last && dist <= self.tolerance) {
+                // the point is reached, go on to the next one
+                self.travelled += dist;
+                remaining -= dist.min(remaining);
+                x = tx;
+                y = ty;
+                self.index += 1;
+            } else {
+                // move as far as possible in the direction of the point
+                x += (tx - x) / dist * remaining;
+                y += (ty - y) / dist * remaining;
+                self.travelled += remaining;
+                break;
+            }
+        }
+
+        transform.set(x, y, z);
+        self.arrived()
+    }
+
+    // returns true if the end of the path is reached
+    pub fn arrived(&self) -> bool {
+        self.index >= self.points.len()
+    }
+
+    // returns the progress along the path from 0.0 to 1.0
+    pub fn progress(&self) -> f32 {
+        if self.arrived() {
+            return 1.0;
+        }
+
+        let length = self.length + self.lead_in.unwrap_or(0.0);
+        if length == 0.0 {
+            return 0.0;
+        }
+
+        (self.travelled / length).min(1.0)
+    }
+
+    // returns the point the follower is currently moving to
+    pub fn target(&self) -> Option<(f32, f32)> {
+        self.points.get(self.index).copied()
+    }
+
+    // returns the length of the path from the first to the last point
+    pub fn length(&self) -> f32 {
+        self.length
+    }
+
+    // start again from the first point
+    pub fn reset(&mut self) {
+        self.index = 0;
+        self.lead_in = None;
+        self.travelled = 0.0;
+    }
+}
+
+// out of bounds cells are treated as solid
+fn blocked(maze: &dyn Maze, x: isize, y: isize) -> bool {
+    if x < 0 || y < 0 || maze.width() <= x as usize || maze.height() <= y as usize {
+        return true;
+    }
+
+    maze.solid(x as usize, y as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::math::pathfinding::astar;
+
+    struct Room {}
+
+    impl Maze for Room {
+        fn width(&self) -> usize {
+            10
+        }
+
+        fn height(&self) -> usize {
+            10
+        }
+
+        fn solid(&self, x: usize, y: usize) -> bool {
+            x == 5 && y < 8
+        }
+    }
+
+    #[test]
+    fn test_line_of_sight() {
+        assert!(line_of_sight(&Room {}, (0, 0), (4, 9)));
+        assert!(!line_of_sight(&Room {}, (0, 0), (9, 0)));
+        assert!(line_of_sight(&Room {}, (0, 8), (9, 8)));
+    }
+
+    #[test]
+    fn test_string_pull() {
+        let path = astar(&Room {}, (0, 0), (9, 0)).unwrap();
+        let pulled = string_pull(&Room {}, &path);
+
+        assert_eq!(Some(&(0, 0)), pulled.first());
+        assert_eq!(Some(&(9, 0)), pulled.last());
+        assert!(pulled.len() < path.len());
+
+        // every kept cell is still a cell of the original path
+        for cell in pulled.iter() {
+            assert!(path.contains(cell));
+        }
+    }
+
+    #[test]
+    fn test_smooth() {
+        let path = vec![(0, 0), (1, 0), (1, 1)];
+        let smoothed = smooth(&path, 32.0, 4);
+        assert_eq!(9, smoothed.len());
+        assert_eq!((16.0, 16.0), smoothed[0]);
+        assert_eq!((48.0, 16.0), smoothed[4]);
+        assert_eq!((48.0, 48.0), smoothed[8]);
+    }
+
+    #[test]
+    fn test_path_follower() {
+        let mut transform = Transform::new();
+        let mut follower = PathFollower::new(vec![(0.0, 0.0), (10.0, 0.0), (10.0, 10.0)], 5.0, 0.1);
+        assert_eq!(20.0, follower.length());
+
+        assert!(!follower.update(&mut transform, 1.0));
+        assert_eq!(0.25, follower.progress());
+        assert_eq!((5.0, 0.0, 0.0), transform.pos());
+
+        assert!(!follower.update(&mut transform, 2.0));
+        assert_eq!((10.0, 5.0, 0.0), transform.pos());
+
+        assert!(follower.update(&mut transform, 10.0));
+        assert_eq!(1.0, follower.progress());
+        assert_eq!((10.0, 10.0, 0.0), transform.pos());
+
+        // the way from the start to the first point is part of the progress
+        let mut transform = Transform::new();
+        transform.set(-10.0, 0.0, 0.0);
+        let mut follower = PathFollower::new(vec![(0.0, 0.0), (10.0, 0.0)], 5.0, 0.1);
+        assert!(!follower.update(&mut transform, 1.0));
+        assert_eq!(0.25, follower.progress());
+        assert!(!follower.update(&mut transform, 2.0));
+        assert_eq!(0.75, follower.progress());
+        assert_eq!(10.0, follower.length());
+    }
+}
@@ -1,3 +1,5 @@
+pub mod hierarchical;
+
 /**
 Implement Maze for an Object,
 so you can use the A star pathfinding algorithm.
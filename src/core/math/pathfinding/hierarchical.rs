@@ -0,0 +1,653 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use crate::core::math::pathfinding::Maze;
+
+// movement costs, diagonal steps cost about sqrt(2) times more
+const STRAIGHT: u32 = 10;
+const DIAGONAL: u32 = 14;
+
+// the 8 adjacent tiles, same as the A star algorithm
+const DIRECTIONS: [(isize, isize); 8] = [(-1,-1), (0,-1), (1,-1), (-1, 0), (1, 0), (-1,1), (0,1), (1,1)];
+
+// entrances longer than this get a node on both ends
+const LONG_ENTRANCE: usize = 6;
+
+/**
+Hierarchical pathfinding (HPA*) for large mazes.
+
+the maze is split into square chunks, where the borders between
+two chunks can be crossed an entrance node is created.
+the nodes inside a chunk are connected with their walking cost,
+so a long path is first searched on this small abstract graph
+and then refined into a path of cells chunk by chunk.
+
+the hierarchy does not hold the maze, pass the same maze
+to every method. when tiles change call update so only
+the affected chunk is rebuilt.
+*/
+pub struct Hierarchy {
+    width: usize,
+    height: usize,
+    chunk_size: usize,
+    chunks_x: usize,
+    chunks_y: usize,
+    nodes: Vec<Option<Node>>,
+    free: Vec<usize>,
+    chunk_nodes: Vec<Vec<usize>>,
+    borders: HashMap<(usize, usize), Vec<usize>>,
+}
+
+// an entrance cell on the border of a chunk
+struct Node {
+    pos: (usize, usize),
+    chunk: usize,
+    edges: Vec<Edge>,
+}
+
+// a connection in the abstract graph,
+// inter edges cross the border between two chunks
+#[derive(Clone, Copy)]
+struct Edge {
+    to: usize,
+    cost: u32,
+    inter: bool,
+}
+
+// a rectangle of cells
+#[derive(Clone, Copy)]
+struct Bounds {
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+}
+
+impl Bounds {
+    fn contains(&self, x: isize, y: isize) -> bool {
+        let in_x = self.x as isize <= x && x < (self.x + self.width) as isize;
+        let in_y = self.y as isize <= y && y < (self.y + self.height) as isize;
+        in_x && in_y
+    }
+
+    // the index of a cell inside the bounds
+    fn index(&self, pos: (usize, usize)) -> usize {
+        (pos.1 - self.y) * self.width + (pos.0 - self.x)
+    }
+
+    // the cell of an index inside the bounds
+    fn pos(&self, i: usize) -> (usize, usize) {
+        (self.x + i % self.width, self.y + i / self.width)
+    }
+}
+
+impl Hierarchy {
+    /*
+    create the abstract graph for a maze
+    chunk_size is the width and height of a chunk in tiles
+    */
+    pub fn new(maze: &dyn Maze, chunk_size: usize) -> Hierarchy {
+        let chunk_size = chunk_size.max(1);
+        let width = maze.width();
+        let height = maze.height();
+        let chunks_x = width.div_ceil(chunk_size);
+        let chunks_y = height.div_ceil(chunk_size);
+
+        let mut hierarchy = Hierarchy {
+            width, height, chunk_size, chunks_x, chunks_y,
+            nodes: vec![],
+            free: vec![],
+            chunk_nodes: vec![vec![]; chunks_x * chunks_y],
+            borders: HashMap::new(),
+        };
+
+        // find the entrances between all chunks
+        for cy in 0..chunks_y {
+            for cx in 0..chunks_x {
+                let chunk = cy * chunks_x + cx;
+
+                if cx + 1 < chunks_x {
+                    hierarchy.build_border(maze, chunk, chunk + 1);
+                }
+
+                if cy + 1 < chunks_y {
+                    hierarchy.build_border(maze, chunk, chunk + chunks_x);
+                }
+            }
+        }
+
+        // connect the entrances inside every chunk
+        for chunk in 0..chunks_x * chunks_y {
+            hierarchy.connect_chunk(maze, chunk);
+        }
+
+        hierarchy
+    }
+
+    /*
+    call this after the tile x, y of the maze changed
+    only the chunk of the tile and its borders are rebuilt
+    */
+    pub fn update(&mut self, maze: &dyn Maze, x: usize, y: usize) -> Result<(), String> {
+        if x >= self.width || y >= self.height {
+            return Err(format!("Tile {}, {} is not in the maze.", x, y));
+        }
+
+        self.rebuild_chunk(maze, x / self.chunk_size, y / self.chunk_size)
+    }
+
+    // rebuild the entrances and connections of the chunk cx, cy
+    pub fn rebuild_chunk(&mut self, maze: &dyn Maze, cx: usize, cy: usize) -> Result<(), String> {
+        if cx >= self.chunks_x || cy >= self.chunks_y {
+            return Err(format!("Chunk {}, {} does not exist.", cx, cy));
+        }
+
+        let chunk = cy * self.chunks_x + cx;
+        let neighbours = self.neighbour_chunks(chunk);
+
+        for neighbour in neighbours.iter() {
+            let key = (chunk.min(*neighbour), chunk.max(*neighbour));
+            self.remove_border(key);
+            self.build_border(maze, key.0, key.1);
+        }
+
+        // the entrances of the neighbours changed as well
+        self.connect_chunk(maze, chunk);
+        for neighbour in neighbours {
+            self.connect_chunk(maze, neighbour);
+        }
+
+        Ok(())
+    }
+
+    /*
+    find a path from start to end
+    returns the cells of the path like the A star algorithm
+    */
+    pub fn find(&self, maze: &dyn Maze, start: (usize, usize), end: (usize, usize)) -> Option<Vec<(usize, usize)>> {
+        if !self.walkable(maze, start) || !self.walkable(maze, end) {
+            return None;
+        }
+
+        if start == end {
+            return Some(vec![start]);
+        }
+
+        let start_chunk = self.chunk_of(start);
+        let end_chunk = self.chunk_of(end);
+
+        // a path inside a single chunk needs no abstract search
+        if start_chunk == end_chunk {
+            if let Some((path, _)) = local_astar(maze, start, end, self.bounds(start_chunk)) {
+                return Some(path);
+            }
+        }
+
+        // connect start and end to the entrances of their chunks
+        let start_edges = self.chunk_costs(maze, start, start_chunk);
+        let end_costs: HashMap<usize, u32> = self.chunk_costs(maze, end, end_chunk)
+            .into_iter()
+            .map(|edge| (edge.to, edge.cost))
+            .collect();
+
+        let abstract_path = self.abstract_search(start, end, &start_edges, &end_costs)?;
+
+        // refine the abstract path into a path of cells
+        let mut path = vec![start];
+        for pair in abstract_path.windows(2) {
+            let (from, to) = (pair[0], pair[1]);
+
+            if from == to {
+                continue;
+            }
+
+            if self.chunk_of(from) != self.chunk_of(to) {
+                // an inter edge, the cells are adjacent
+                path.push(to);
+                continue;
+            }
+
+            let (segment, _) = local_astar(maze, from, to, self.bounds(self.chunk_of(from)))?;
+            path.extend_from_slice(&segment[1..]);
+        }
+
+        Some(path)
+    }
+
+    // the number of entrance nodes in the abstract graph
+    pub fn node_count(&self) -> usize {
+        self.nodes.iter().filter(|node| node.is_some()).count()
+    }
+
+    // returns the number of chunks in x and y direction
+    pub fn chunks(&self) -> (usize, usize) {
+        (self.chunks_x, self.chunks_y)
+    }
+
+    // A star on the abstract graph, returns the positions of the visited nodes
+    fn abstract_search(&self, start: (usize, usize), end: (usize, usize), start_edges: &[Edge], end_costs: &HashMap<usize, u32>) -> Option<Vec<(usize, usize)>> {
+        // two extra ids for the temporary start and end nodes
+        let start_id = self.nodes.len();
+        let end_id = self.nodes.len() + 1;
+
+        let pos = |id: usize| -> (usize, usize) {
+            if id == start_id {
+                start
+            } else if id == end_id {
+                end
+            } else {
+                self.node(id).pos
+            }
+        };
+
+        let mut open = BinaryHeap::new();
+        let mut costs: HashMap<usize, u32> = HashMap::new();
+        let mut parents: HashMap<usize, usize> = HashMap::new();
+
+        open.push(Reverse((octile(start, end), start_id)));
+        costs.insert(start_id, 0);
+
+        while let Some(Reverse((f, current))) = open.pop() {
+            if current == end_id {
+                // walk back along the parents
+                let mut path = vec![end];
+                let mut id = end_id;
+                while let Some(parent) = parents.get(&id) {
+                    path.push(pos(*parent));
+                    id = *parent;
+                }
+
+                path.reverse();
+                return Some(path);
+            }
+
+            let g = costs[&current];
+            if f > g + octile(pos(current), end) {
+                // an outdated entry of the open list
+                continue;
+            }
+
+            let mut edges = if current == start_id {
+                start_edges.to_vec()
+            } else {
+                self.node(current).edges.clone()
+            };
+
+            if let Some(cost) = end_costs.get(&current) {
+                edges.push(Edge {to: end_id, cost: *cost, inter: false});
+            }
+
+            for edge in edges {
+                let cost = g + edge.cost;
+                if costs.get(&edge.to).is_none_or(|old| cost < *old) {
+                    costs.insert(edge.to, cost);
+                    parents.insert(edge.to, current);
+                    open.push(Reverse((cost + octile(pos(edge.to), end), edge.to)));
+                }
+            }
+        }
+
+        None
+    }
+
+    // the walking costs from a cell to all entrances of a chunk
+    fn chunk_costs(&self, maze: &dyn Maze, from: (usize, usize), chunk: usize) -> Vec<Edge> {
+        let bounds = self.bounds(chunk);
+        let costs = local_costs(maze, from, bounds);
+
+        self.chunk_nodes[chunk]
+            .iter()
+            .filter_map(|id| {
+                let cost = costs[bounds.index(self.node(*id).pos)];
+                if cost == u32::MAX {
+                    None
+                } else {
+                    Some(Edge {to: *id, cost, inter: false})
+                }
+            })
+            .collect()
+    }
+
+    // create the entrance nodes on the border between chunk a and b
+    fn build_border(&mut self, maze: &dyn Maze, a: usize, b: usize) {
+        let bounds_a = self.bounds(a);
+        let bounds_b = self.bounds(b);
+
+        // the pairs of adjacent cells along the border
+        let pairs: Vec<((usize, usize), (usize, usize))> = if b == a + 1 {
+            let x = bounds_a.x + bounds_a.width - 1;
+            (bounds_a.y..bounds_a.y + bounds_a.height)
+                .map(|y| ((x, y), (bounds_b.x, y)))
+                .collect()
+        } else {
+            let y = bounds_a.y + bounds_a.height - 1;
+            (bounds_a.x..bounds_a.x + bounds_a.width)
+                .map(|x| ((x, y), (x, bounds_b.y)))
+                .collect()
+        };
+
+        // search for runs of cells that are open on both sides
+        let mut entrances = vec![];
+        let mut run: Vec<((usize, usize), (usize, usize))> = vec![];
+
+        for pair in pairs.into_iter().map(Some).chain(std::iter::once(None)) {
+            if let Some((pa, pb)) = pair {
+                if !maze.solid(pa.0, pa.1) && !maze.solid(pb.0, pb.1) {
+                    run.push((pa, pb));
+                    continue;
+                }
+            }
+
+            if run.is_empty() {
+                continue;
+            }
+
+            if run.len() < LONG_ENTRANCE {
+                entrances.push(run[run.len() / 2]);
+            } else {
+                entrances.push(run[0]);
+                entrances.push(run[run.len() - 1]);
+            }
+
+            run.clear();
+        }
+
+        let mut ids = vec![];
+        for (pa, pb) in entrances {
+            let id_a = self.add_node(pa, a);
+            let id_b = self.add_node(pb, b);
+            let cost = STRAIGHT;
+            self.node_mut(id_a).edges.push(Edge {to: id_b, cost, inter: true});
+            self.node_mut(id_b).edges.push(Edge {to: id_a, cost, inter: true});
+            ids.push(id_a);
+            ids.push(id_b);
+        }
+
+        self.borders.insert((a, b), ids);
+    }
+
+    // remove all entrance nodes on the border between two chunks
+    fn remove_border(&mut self, key: (usize, usize)) {
+        let ids = match self.borders.remove(&key) {
+            Some(ids) => ids,
+            None => return,
+        };
+
+        for id in ids.iter() {
+            let chunk = self.node(*id).chunk;
+            self.chunk_nodes[chunk].retain(|other| other != id);
+            self.nodes[*id] = None;
+            self.free.push(*id);
+        }
+
+        // remove the edges pointing to the removed nodes
+        for chunk in [key.0, key.1] {
+            for other in self.chunk_nodes[chunk].clone() {
+                self.node_mut(other).edges.retain(|edge| !ids.contains(&edge.to));
+            }
+        }
+    }
+
+    // recreate the intra edges between the entrances of a chunk
+    fn connect_chunk(&mut self, maze: &dyn Maze, chunk: usize) {
+        let ids = self.chunk_nodes[chunk].clone();
+
+        for id in ids.iter() {
+            self.node_mut(*id).edges.retain(|edge| edge.inter);
+        }
+
+        for id in ids.iter() {
+            let pos = self.node(*id).pos;
+            let edges: Vec<Edge> = self.chunk_costs(maze, pos, chunk)
+                .into_iter()
+                .filter(|edge| edge.to != *id)
+                .collect();
+            self.node_mut(*id).edges.extend(edges);
+        }
+    }
+
+    // add a node and reuse a free slot if possible
+    fn add_node(&mut self, pos: (usize, usize), chunk: usize) -> usize {
+        let node = Node {pos, chunk, edges: vec![]};
+
+        let id = match self.free.pop() {
+            Some(id) => {
+                self.nodes[id] = Some(node);
+                id
+            },
+            None => {
+                self.nodes.push(Some(node));
+                self.nodes.len() - 1
+            },
+        };
+
+        self.chunk_nodes[chunk].push(id);
+        id
+    }
+
+    fn node(&self, id: usize) -> &Node {
+        self.nodes[id].as_ref().expect("removed node is still referenced")
+    }
+
+    fn node_mut(&mut self, id: usize) -> &mut Node {
+        self.nodes[id].as_mut().expect("removed node is still referenced")
+    }
+
+    // the chunks left, right, above and below a chunk
+    fn neighbour_chunks(&self, chunk: usize) -> Vec<usize> {
+        let (cx, cy) = (chunk % self.chunks_x, chunk / self.chunks_x);
+        let mut neighbours = vec![];
+
+        if cx > 0 {
+            neighbours.push(chunk - 1);
+        }
+
+        if cx + 1 < self.chunks_x {
+            neighbours.push(chunk + 1);
+        }
+
+        if cy > 0 {
+            neighbours.push(chunk - self.chunks_x);
+        }
+
+        if cy + 1 < self.chunks_y {
+            neighbours.push(chunk + self.chunks_x);
+        }
+
+        neighbours
+    }
+
+    // the cells of a chunk, chunks at the edge of the maze may be smaller
+    fn bounds(&self, chunk: usize) -> Bounds {
+        let x = (chunk % self.chunks_x) * self.chunk_size;
+        let y = (chunk / self.chunks_x) * self.chunk_size;
+        let width = self.chunk_size.min(self.width - x);
+        let height = self.chunk_size.min(self.height - y);
+        Bounds {x, y, width, height}
+    }
+
+    fn chunk_of(&self, pos: (usize, usize)) -> usize {
+        (pos.1 / self.chunk_size) * self.chunks_x + pos.0 / self.chunk_size
+    }
+
+    fn walkable(&self, maze: &dyn Maze, pos: (usize, usize)) -> bool {
+        pos.0 < self.width && pos.1 < self.height && !maze.solid(pos.0, pos.1)
+    }
+}
+
+// the distance between two cells with diagonal movement
+fn octile(a: (usize, usize), b: (usize, usize)) -> u32 {
+    let dx = (a.0 as isize - b.0 as isize).unsigned_abs() as u32;
+    let dy = (a.1 as isize - b.1 as isize).unsigned_abs() as u32;
+    STRAIGHT * dx.max(dy) + (DIAGONAL - STRAIGHT) * dx.min(dy)
+}
+
+// the walkable adjacent cells inside the bounds and the cost to walk there
+fn adjacent(maze: &dyn Maze, pos: (usize, usize), bounds: Bounds) -> Vec<((usize, usize), u32)> {
+    let mut cells = vec![];
+
+    for dir in DIRECTIONS {
+        let x = pos.0 as isize + dir.0;
+        let y = pos.1 as isize + dir.1;
+
+        if !bounds.contains(x, y) || maze.solid(x as usize, y as usize) {
+            continue;
+        }
+
+        let cost = if dir.0 != 0 && dir.1 != 0 { DIAGONAL } else { STRAIGHT };
+        cells.push(((x as usize, y as usize), cost));
+    }
+
+    cells
+}
+
+// dijkstra from a cell, returns the cost to every cell of the bounds
+fn local_costs(maze: &dyn Maze, start: (usize, usize), bounds: Bounds) -> Vec<u32> {
+    let mut costs = vec![u32::MAX; bounds.width * bounds.height];
+    let mut open = BinaryHeap::new();
+
+    costs[bounds.index(start)] = 0;
+    open.push(Reverse((0, bounds.index(start))));
+
+    while let Some(Reverse((cost, i))) = open.pop() {
+        if cost > costs[i] {
+            continue;
+        }
+
+        for (pos, step) in adjacent(maze, bounds.pos(i), bounds) {
+            let j = bounds.index(pos);
+            if cost + step < costs[j] {
+                costs[j] = cost + step;
+                open.push(Reverse((cost + step, j)));
+            }
+        }
+    }
+
+    costs
+}
+
+// A star that does not leave the bounds, returns the path and its cost
+fn local_astar(maze: &dyn Maze, start: (usize, usize), end: (usize, usize), bounds: Bounds) -> Option<(Vec<(usize, usize)>, u32)> {
+    let mut costs = vec![u32::MAX; bounds.width * bounds.height];
+    let mut parents = vec![usize::MAX; bounds.width * bounds.height];
+    let mut open = BinaryHeap::new();
+
+    let start_index = bounds.index(start);
+    let end_index = bounds.index(end);
+    costs[start_index] = 0;
+    open.push(Reverse((octile(start, end), start_index)));
+
+    while let Some(Reverse((f, i))) = open.pop() {
+        let pos = bounds.pos(i);
+
+        if i == end_index {
+            let mut path = vec![end];
+            let mut current = i;
+            while parents[current] != usize::MAX {
+                current = parents[current];
+                path.push(bounds.pos(current));
+            }
+
+            path.reverse();
+            return Some((path, costs[end_index]));
+        }
+
+        if f > costs[i] + octile(pos, end) {
+            continue;
+        }
+
+        for (next, step) in adjacent(maze, pos, bounds) {
+            let j = bounds.index(next);
+            let cost = costs[i] + step;
+            if cost < costs[j] {
+                costs[j] = cost;
+                parents[j] = i;
+                open.push(Reverse((cost + octile(next, end), j)));
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a maze with walls that have a single gap each
+    struct Walls {
+        size: usize,
+        blocked: Vec<(usize, usize)>,
+    }
+
+    impl Maze for Walls {
+        fn width(&self) -> usize {
+            self.size
+        }
+
+        fn height(&self) -> usize {
+            self.size
+        }
+
+        fn solid(&self, x: usize, y: usize) -> bool {
+            if self.blocked.contains(&(x, y)) {
+                return true;
+            }
+
+            // vertical walls every 10 tiles,
+            // the gap is at the top or the bottom
+            if x % 10 == 5 {
+                let gap = if (x / 10).is_multiple_of(2) { self.size - 1 } else { 0 };
+                return y != gap;
+            }
+
+            false
+        }
+    }
+
+    // checks that a path is connected and walkable
+    fn valid(maze: &dyn Maze, path: &[(usize, usize)], start: (usize, usize), end: (usize, usize)) -> bool {
+        let connected = path.windows(2).all(|pair| {
+            let dx = (pair[0].0 as isize - pair[1].0 as isize).abs();
+            let dy = (pair[0].1 as isize - pair[1].1 as isize).abs();
+            dx <= 1 && dy <= 1
+        });
+
+        let walkable = path.iter().all(|(x, y)| !maze.solid(*x, *y));
+        connected && walkable && path.first() == Some(&start) && path.last() == Some(&end)
+    }
+
+    #[test]
+    fn test_hierarchy() {
+        let maze = Walls {size: 40, blocked: vec![]};
+        let hierarchy = Hierarchy::new(&maze, 8);
+        assert_eq!((5, 5), hierarchy.chunks());
+        assert!(hierarchy.node_count() > 0);
+
+        let path = hierarchy.find(&maze, (0, 0), (39, 39)).unwrap();
+        assert!(valid(&maze, &path, (0, 0), (39, 39)));
+
+        let path = hierarchy.find(&maze, (1, 1), (3, 2)).unwrap();
+        assert!(valid(&maze, &path, (1, 1), (3, 2)));
+        assert_eq!(None, hierarchy.find(&maze, (5, 5), (0, 0)));
+    }
+
+    #[test]
+    fn test_hierarchy_update() {
+        let mut maze = Walls {size: 40, blocked: vec![]};
+        let mut hierarchy = Hierarchy::new(&maze, 8);
+        assert!(hierarchy.find(&maze, (0, 0), (9, 0)).is_some());
+
+        // close the only gap of the first wall
+        maze.blocked.push((5, 39));
+        hierarchy.update(&maze, 5, 39).unwrap();
+        assert_eq!(None, hierarchy.find(&maze, (0, 0), (9, 0)));
+
+        // and open it again
+        maze.blocked.clear();
+        hierarchy.update(&maze, 5, 39).unwrap();
+        let path = hierarchy.find(&maze, (0, 0), (9, 0)).unwrap();
+        assert!(valid(&maze, &path, (0, 0), (9, 0)));
+
+        assert!(hierarchy.update(&maze, 40, 0).is_err());
+    }
+}
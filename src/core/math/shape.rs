@@ -0,0 +1,372 @@
+use std::f32::consts::{PI, TAU};
+
+/*
+every shape is returned as a list of triangles,
+three points after each other form one triangle.
+angles are in radians and go clockwise on the screen,
+because y points down
+*/
+
+// how the open ends of a line look
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Cap {
+    Butt,
+    Square,
+    Round,
+}
+
+// how two segments of a line are connected
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Join {
+    Miter,
+    Bevel,
+    Round,
+}
+
+// a miter longer than this times the half thickness becomes a bevel
+const MITER_LIMIT: f32 = 4.0;
+
+fn sub(a: (f32, f32), b: (f32, f32)) -> (f32, f32) {
+    (a.0 - b.0, a.1 - b.1)
+}
+
+fn add(a: (f32, f32), b: (f32, f32)) -> (f32, f32) {
+    (a.0 + b.0, a.1 + b.1)
+}
+
+fn scale(a: (f32, f32), s: f32) -> (f32, f32) {
+    (a.0 * s, a.1 * s)
+}
+
+fn cross(a: (f32, f32), b: (f32, f32)) -> f32 {
+    a.0 * b.1 - a.1 * b.0
+}
+
+fn normalize(a: (f32, f32)) -> (f32, f32) {
+    let length = (a.0 * a.0 + a.1 * a.1).sqrt();
+    if length == 0.0 { (0.0, 0.0) } else { (a.0 / length, a.1 / length) }
+}
+
+// the number of segments for a round part of a shape
+fn segments(radius: f32, sweep: f32) -> usize {
+    let full = (radius.max(1.0).sqrt() * 8.0).clamp(16.0, 128.0);
+    ((sweep.abs() / TAU) * full).ceil().max(1.0) as usize
+}
+
+// triangles of a fan around a center between two angles
+fn fan(center: (f32, f32), radius: f32, start: f32, end: f32) -> Vec<(f32, f32)> {
+    let points = arc(center, radius, start, end);
+    let mut triangles = vec![];
+    for pair in points.windows(2) {
+        triangles.extend_from_slice(&[center, pair[0], pair[1]]);
+    }
+    triangles
+}
+
+// the points on a circle between two angles
+pub fn arc(center: (f32, f32), radius: f32, start: f32, end: f32) -> Vec<(f32, f32)> {
+    arc_points(center, radius, start, end, segments(radius, end - start))
+}
+
+fn arc_points(center: (f32, f32), radius: f32, start: f32, end: f32, segments: usize) -> Vec<(f32, f32)> {
+    (0..=segments).map(|i| {
+        let angle = start + (end - start) * i as f32 / segments as f32;
+        (center.0 + angle.cos() * radius, center.1 + angle.sin() * radius)
+    }).collect()
+}
+
+/**
+a line with a thickness along points.
+a closed line also connects the last and the first point
+and has no caps. the segments overlap on the inner side
+of the corners, which shows when the line is transparent
+*/
+pub fn polyline(points: &[(f32, f32)], thickness: f32, cap: Cap, join: Join, closed: bool) -> Vec<(f32, f32)> {
+    // points on top of each other have no direction
+    let mut points: Vec<(f32, f32)> = points.to_vec();
+    points.dedup();
+    if closed && points.len() > 1 && points.first() == points.last() {
+        points.pop();
+    }
+
+    let half = thickness / 2.0;
+    let mut triangles = vec![];
+    if points.len() < 2 || half <= 0.0 {
+        return triangles;
+    }
+
+    let count = if closed { points.len() } else { points.len() - 1 };
+    let segment = |i: usize| (points[i], points[(i + 1) % points.len()]);
+    let normal = |i: usize| {
+        let (a, b) = segment(i);
+        let direction = normalize(sub(b, a));
+        (-direction.1 * half, direction.0 * half)
+    };
+
+    // a quad for every segment
+    for i in 0..count {
+        let (a, b) = segment(i);
+        let n = normal(i);
+        triangles.extend_from_slice(&[add(a, n), sub(a, n), add(b, n)]);
+        triangles.extend_from_slice(&[sub(a, n), sub(b, n), add(b, n)]);
+    }
+
+    // fill the gap on the outer side of every corner
+    let corners = if closed { 0..count } else { 1..count };
+    for i in corners {
+        let before = (i + count - 1) % count;
+        let p = points[i];
+        let turn = cross(normal(before), normal(i));
+        if turn.abs() < 1e-6 {
+            continue;
+        }
+
+        // the outer side is on the left of a right turn
+        let side = if turn > 0.0 { -1.0 } else { 1.0 };
+        let o0 = scale(normal(before), side);
+        let o1 = scale(normal(i), side);
+
+        match join {
+            Join::Bevel => triangles.extend_from_slice(&[p, add(p, o0), add(p, o1)]),
+            Join::Miter => {
+                let direction = normalize(add(o0, o1));
+                let cos = (direction.0 * o0.0 + direction.1 * o0.1) / half;
+                if cos <= 1.0 / MITER_LIMIT {
+                    triangles.extend_from_slice(&[p, add(p, o0), add(p, o1)]);
+                } else {
+                    let miter = add(p, scale(direction, half / cos));
+                    triangles.extend_from_slice(&[p, add(p, o0), miter]);
+                    triangles.extend_from_slice(&[p, miter, add(p, o1)]);
+                }
+            },
+            Join::Round => {
+                let start = o0.1.atan2(o0.0);
+                let mut sweep = o1.1.atan2(o1.0) - start;
+                // go the short way around
+                if sweep > PI { sweep -= TAU; }
+                if sweep < -PI { sweep += TAU; }
+                triangles.extend(fan(p, half, start, start + sweep));
+            },
+        }
+    }
+
+    if !closed {
+        let last = points.len() - 1;
+        for (p, n, backward) in [(points[0], normal(0), true), (points[last], normal(count - 1), false)] {
+            // the direction the cap points to
+            let out = if backward { (-n.1, n.0) } else { (n.1, -n.0) };
+            match cap {
+                Cap::Butt => (),
+                Cap::Square => {
+                    let q = add(p, out);
+                    triangles.extend_from_slice(&[add(p, n), sub(p, n), add(q, n)]);
+                    triangles.extend_from_slice(&[sub(p, n), sub(q, n), add(q, n)]);
+                },
+                Cap::Round => {
+                    let start = n.1.atan2(n.0);
+                    let sweep = if backward { PI } else { -PI };
+                    triangles.extend(fan(p, half, start, start + sweep));
+                },
+            }
+        }
+    }
+
+    triangles
+}
+
+// twice the signed area of a polygon
+fn signed_area(points: &[(f32, f32)]) -> f32 {
+    let mut area = 0.0;
+    for i in 0..points.len() {
+        area += cross(points[i], points[(i + 1) % points.len()]);
+    }
+    area
+}
+
+// true if p is inside or on the edge of the triangle abc, which winds positive
+fn in_triangle(p: (f32, f32), a: (f32, f32), b: (f32, f32), c: (f32, f32)) -> bool {
+    cross(sub(b, a), sub(p, a)) >= 0.0 && cross(sub(c, b), sub(p, b)) >= 0.0 && cross(sub(a, c), sub(p, c)) >= 0.0
+}
+
+/**
+split a simple polygon into triangles with ear clipping,
+the polygon can be concave and wind in both directions.
+returns the indices of the points of every triangle
+*/
+pub fn triangulate(points: &[(f32, f32)]) -> Result<Vec<[usize; 3]>, String> {
+    if points.len() < 3 {
+        return Err(format!("A polygon needs at least 3 points, not {}.", points.len()));
+    }
+
+    let area = signed_area(points);
+    if area.abs() < 1e-6 {
+        return Err("A polygon needs an area.".to_string());
+    }
+
+    // walk the polygon so it winds positive
+    let mut remaining: Vec<usize> = (0..points.len()).collect();
+    if area < 0.0 {
+        remaining.reverse();
+    }
+
+    let mut triangles = vec![];
+    while remaining.len() > 3 {
+        let n = remaining.len();
+        let ear = (0..n).find(|&i| {
+            let (a, b, c) = (remaining[(i + n - 1) % n], remaining[i], remaining[(i + 1) % n]);
+            let (pa, pb, pc) = (points[a], points[b], points[c]);
+
+            // a reflex corner is no ear
+            if cross(sub(pb, pa), sub(pc, pb)) <= 0.0 {
+                return false;
+            }
+
+            // no other corner may lie in the ear
+            !remaining.iter()
+                .filter(|&&j| j != a && j != b && j != c)
+                .any(|&j| points[j] != pa && points[j] != pb && points[j] != pc && in_triangle(points[j], pa, pb, pc))
+        });
+
+        match ear {
+            Some(i) => {
+                triangles.push([remaining[(i + n - 1) % n], remaining[i], remaining[(i + 1) % n]]);
+                remaining.remove(i);
+            },
+            None => return Err("The polygon intersects itself.".to_string()),
+        }
+    }
+
+    triangles.push([remaining[0], remaining[1], remaining[2]]);
+    Ok(triangles)
+}
+
+// the triangles of a filled polygon
+pub fn polygon(points: &[(f32, f32)]) -> Result<Vec<(f32, f32)>, String> {
+    let triangles = triangulate(points)?;
+    Ok(triangles.iter().flat_map(|triangle| triangle.map(|i| points[i])).collect())
+}
+
+// the outline of a polygon, the thickness is centered on the edges
+pub fn polygon_outline(points: &[(f32, f32)], thickness: f32, join: Join) -> Vec<(f32, f32)> {
+    polyline(points, thickness, Cap::Butt, join, true)
+}
+
+// a slice of a filled circle
+pub fn pie(center: (f32, f32), radius: f32, start: f32, end: f32) -> Vec<(f32, f32)> {
+    fan(center, radius, start, end)
+}
+
+// the triangles of a rect
+fn quad(x: f32, y: f32, width: f32, height: f32) -> [(f32, f32); 6] {
+    let (right, bottom) = (x + width, y + height);
+    [(x, y), (right, y), (right, bottom), (x, y), (right, bottom), (x, bottom)]
+}
+
+// a ring between two radii and two angles
+fn band(center: (f32, f32), inner: f32, outer: f32, start: f32, end: f32) -> Vec<(f32, f32)> {
+    // both sides need the same number of points
+    let segments = segments(outer, end - start);
+    let inner_points = arc_points(center, inner.max(0.0), start, end, segments);
+    let outer_points = arc_points(center, outer, start, end, segments);
+    let mut triangles = vec![];
+    for i in 0..outer_points.len() - 1 {
+        let (a, b) = (inner_points[i], inner_points[i + 1]);
+        let (c, d) = (outer_points[i], outer_points[i + 1]);
+        triangles.extend_from_slice(&[a, c, d, a, d, b]);
+    }
+    triangles
+}
+
+// an arc with a thickness, centered on the radius
+pub fn arc_outline(center: (f32, f32), radius: f32, start: f32, end: f32, thickness: f32) -> Vec<(f32, f32)> {
+    band(center, radius - thickness / 2.0, radius + thickness / 2.0, start, end)
+}
+
+// the outline of a rect at 0, 0, it stays inside the rect
+pub fn rect_outline(width: f32, height: f32, thickness: f32) -> Vec<(f32, f32)> {
+    let t = thickness.min(width / 2.0).min(height / 2.0);
+    let mut triangles = vec![];
+    triangles.extend_from_slice(&quad(0.0, 0.0, width, t));
+    triangles.extend_from_slice(&quad(0.0, height - t, width, t));
+    triangles.extend_from_slice(&quad(0.0, t, t, height - 2.0 * t));
+    triangles.extend_from_slice(&quad(width - t, t, t, height - 2.0 * t));
+    triangles
+}
+
+// the outline of a circle around radius, radius, it stays inside the circle
+pub fn circle_outline(radius: f32, thickness: f32) -> Vec<(f32, f32)> {
+    band((radius, radius), radius - thickness, radius, 0.0, TAU)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the area covered by a list of triangles
+    fn area(triangles: &[(f32, f32)]) -> f32 {
+        triangles.chunks(3).map(|t| signed_area(t).abs() / 2.0).sum()
+    }
+
+    fn near(a: f32, b: f32, tolerance: f32) -> bool {
+        (a - b).abs() <= tolerance
+    }
+
+    #[test]
+    fn test_triangulate() {
+        let square = [(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)];
+        assert_eq!(2, triangulate(&square).unwrap().len());
+        assert!(near(100.0, area(&polygon(&square).unwrap()), 0.001));
+
+        // an L shape is concave, both windings work
+        let mut l = vec![(0.0, 0.0), (20.0, 0.0), (20.0, 10.0), (10.0, 10.0), (10.0, 30.0), (0.0, 30.0)];
+        assert_eq!(4, triangulate(&l).unwrap().len());
+        assert!(near(400.0, area(&polygon(&l).unwrap()), 0.001));
+        l.reverse();
+        assert!(near(400.0, area(&polygon(&l).unwrap()), 0.001));
+
+        assert!(triangulate(&[(0.0, 0.0), (1.0, 1.0)]).is_err());
+        assert!(triangulate(&[(0.0, 0.0), (1.0, 1.0), (2.0, 2.0)]).is_err());
+    }
+
+    #[test]
+    fn test_polyline() {
+        let line = [(0.0, 0.0), (10.0, 0.0)];
+        assert!(near(20.0, area(&polyline(&line, 2.0, Cap::Butt, Join::Miter, false)), 0.001));
+        // a square cap adds half the thickness on both ends
+        let square = polyline(&line, 2.0, Cap::Square, Join::Miter, false);
+        assert!(near(24.0, area(&square), 0.001));
+        assert!(near(-1.0, square.iter().map(|p| p.0).fold(f32::MAX, f32::min), 0.001));
+        assert!(near(11.0, square.iter().map(|p| p.0).fold(f32::MIN, f32::max), 0.001));
+        // a round cap adds a circle
+        assert!(near(20.0 + PI, area(&polyline(&line, 2.0, Cap::Round, Join::Miter, false)), 0.1));
+
+        // a right angle, the miter fills the corner square
+        let corner = [(0.0, 0.0), (10.0, 0.0), (10.0, 10.0)];
+        let miter = area(&polyline(&corner, 2.0, Cap::Butt, Join::Miter, false));
+        let bevel = area(&polyline(&corner, 2.0, Cap::Butt, Join::Bevel, false));
+        let round = area(&polyline(&corner, 2.0, Cap::Butt, Join::Round, false));
+        assert!(near(40.0 + 1.0, miter, 0.001));
+        assert!(near(40.0 + 0.5, bevel, 0.001));
+        assert!(bevel < round && round < miter);
+
+        assert!(polyline(&[(1.0, 1.0), (1.0, 1.0)], 2.0, Cap::Round, Join::Round, false).is_empty());
+    }
+
+    #[test]
+    fn test_outlines() {
+        let outline = rect_outline(20.0, 10.0, 2.0);
+        assert!(near(200.0 - 16.0 * 6.0, area(&outline), 0.001));
+        assert!(outline.iter().all(|p| p.0 >= -0.001 && p.0 <= 20.001 && p.1 >= -0.001 && p.1 <= 10.001));
+
+        let ring = circle_outline(10.0, 2.0);
+        assert!(near(PI * (100.0 - 64.0), area(&ring), 2.0));
+        let half_ring = arc_outline((0.0, 0.0), 9.0, 0.0, PI, 2.0);
+        assert!(near(area(&ring) / 2.0, area(&half_ring), 0.5));
+
+        let quarter = pie((0.0, 0.0), 10.0, 0.0, PI / 2.0);
+        assert!(near(PI * 25.0, area(&quarter), 1.0));
+        // clockwise on the screen, the quarter ends below the center
+        let end = arc((0.0, 0.0), 10.0, 0.0, PI / 2.0).pop().unwrap();
+        assert!(near(0.0, end.0, 0.001) && near(10.0, end.1, 0.001));
+    }
+}
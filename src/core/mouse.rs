@@ -12,6 +12,7 @@ pub enum MouseWheelState {
 a little structure to hold the data
 from the mouse it comes from sdl2 and is set in lib.rs
 */
+#[derive(Clone)]
 pub struct Mouse {
     pub x: f32,
     pub y: f32,
@@ -0,0 +1,376 @@
+pub mod wfc;
+
+use crate::core::seed::Seed;
+use crate::core::grid::{self, Solid};
+
+/*
+procedural level generators
+
+every generator is driven by a seed, so the same seed
+always creates the same level. the result is a grid of walls
+and floors where all floors are connected with each other,
+it implements Maze so it can be used with the A star algorithm
+*/
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Cell {
+    Wall,
+    Floor,
+}
+
+impl Solid for Cell {
+    fn solid(&self) -> bool {
+        *self != Cell::Floor
+    }
+}
+
+// the grid the generators create, it implements Maze
+pub type Grid = grid::Grid<Cell>;
+
+// the 4 adjacent cells
+const NEIGHBOURS: [(isize, isize); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+impl Grid {
+    // returns all floor cells
+    pub fn floors(&self) -> Vec<(usize, usize)> {
+        self.positions(|cell| *cell == Cell::Floor)
+    }
+
+    // true if every floor can be reached from every other floor
+    pub fn connected(&self) -> bool {
+        self.regions(|cell| *cell == Cell::Floor).len() <= 1
+    }
+
+    // turn every floor that is not part of the largest region into a wall
+    fn keep_largest_region(&mut self) {
+        let largest = self
+            .regions(|cell| *cell == Cell::Floor)
+            .into_iter()
+            // the first of the largest regions
+            .rev()
+            .max_by_key(|region| region.len())
+            .unwrap_or_default();
+
+        self.fill(Cell::Wall);
+        for (x, y) in largest {
+            self.set(x, y, Cell::Floor);
+        }
+    }
+
+    // carve a horizontal and a vertical corridor between two cells
+    fn carve_corridor(&mut self, from: (usize, usize), to: (usize, usize), horizontal_first: bool) {
+        let corner = if horizontal_first { (to.0, from.1) } else { (from.0, to.1) };
+
+        for (a, b) in [(from, corner), (corner, to)] {
+            for x in a.0.min(b.0)..=a.0.max(b.0) {
+                for y in a.1.min(b.1)..=a.1.max(b.1) {
+                    self.set(x, y, Cell::Floor);
+                }
+            }
+        }
+    }
+}
+
+/*
+rooms and corridors
+the grid is split recursively (binary space partitioning)
+until the parts are smaller than min_size, every part gets a room
+and the rooms of two parts are connected with a corridor
+*/
+pub fn bsp_dungeon(width: usize, height: usize, min_size: usize, seed: &mut Seed) -> Grid {
+    let mut grid = Grid::new(width, height, Cell::Wall);
+    // a room needs at least 3 cells and a wall on each side
+    let min_size = min_size.max(5);
+
+    if width >= 5 && height >= 5 {
+        split(&mut grid, (0, 0, width, height), min_size, seed);
+    }
+
+    grid
+}
+
+// split a part and return the center of one of its rooms
+fn split(grid: &mut Grid, part: (usize, usize, usize, usize), min_size: usize, seed: &mut Seed) -> (usize, usize) {
+    let (x, y, width, height) = part;
+    let split_x = width >= min_size * 2;
+    let split_y = height >= min_size * 2;
+
+    if !split_x && !split_y {
+        // create a room inside the part
+        let room_width = seed.range_usize(3, width - 1);
+        let room_height = seed.range_usize(3, height - 1);
+        let room_x = x + 1 + seed.range_usize(0, width - 1 - room_width);
+        let room_y = y + 1 + seed.range_usize(0, height - 1 - room_height);
+
+        for ry in room_y..room_y + room_height {
+            for rx in room_x..room_x + room_width {
+                grid.set(rx, ry, Cell::Floor);
+            }
+        }
+
+        return (room_x + room_width / 2, room_y + room_height / 2);
+    }
+
+    // split along the longer side if only one side is possible
+    let vertical = if split_x && split_y { seed.next_bool() } else { split_x };
+
+    let (a, b) = if vertical {
+        let at = seed.range_usize(min_size, width - min_size + 1);
+        ((x, y, at, height), (x + at, y, width - at, height))
+    } else {
+        let at = seed.range_usize(min_size, height - min_size + 1);
+        ((x, y, width, at), (x, y + at, width, height - at))
+    };
+
+    let center_a = split(grid, a, min_size, seed);
+    let center_b = split(grid, b, min_size, seed);
+    let horizontal_first = seed.next_bool();
+    grid.carve_corridor(center_a, center_b, horizontal_first);
+
+    if seed.next_bool() { center_a } else { center_b }
+}
+
+/*
+natural looking caves with a cellular automaton
+fill is the chance that a cell starts as wall (about 0.45),
+every step a cell becomes a wall if most of its neighbours are walls.
+only the largest cave is kept so every floor is reachable
+*/
+pub fn cave(width: usize, height: usize, fill: f64, steps: usize, seed: &mut Seed) -> Grid {
+    let mut grid = Grid::new(width, height, Cell::Wall);
+
+    for y in 1..height.saturating_sub(1) {
+        for x in 1..width.saturating_sub(1) {
+            if !seed.chance(fill) {
+                grid.set(x, y, Cell::Floor);
+            }
+        }
+    }
+
+    for _ in 0..steps {
+        let mut next = grid.clone();
+
+        for y in 1..height.saturating_sub(1) {
+            for x in 1..width.saturating_sub(1) {
+                let mut walls = 0;
+                for dy in -1..=1 {
+                    for dx in -1..=1 {
+                        let nx = (x as isize + dx) as usize;
+                        let ny = (y as isize + dy) as usize;
+                        if (dx != 0 || dy != 0) && grid.get(nx, ny) == Some(&Cell::Wall) {
+                            walls += 1;
+                        }
+                    }
+                }
+
+                let wall = match grid.get(x, y) {
+                    Some(Cell::Wall) => walls >= 4,
+                    _ => walls >= 5,
+                };
+                next.set(x, y, if wall { Cell::Wall } else { Cell::Floor });
+            }
+        }
+
+        grid = next;
+    }
+
+    grid.keep_largest_region();
+    grid
+}
+
+/*
+a walker starts in the center and moves in random directions
+carving floors until coverage (0.0 to 1.0) of the grid is floor
+*/
+pub fn drunkard_walk(width: usize, height: usize, coverage: f64, seed: &mut Seed) -> Grid {
+    let mut grid = Grid::new(width, height, Cell::Wall);
+
+    if width < 3 || height < 3 {
+        return grid;
+    }
+
+    let inner = (width - 2) * (height - 2);
+    let target = ((inner as f64 * coverage.clamp(0.0, 1.0)) as usize).max(1);
+    let (mut x, mut y) = (width / 2, height / 2);
+    let mut carved = 0;
+
+    // stop at some point, even if the coverage is not reached
+    for _ in 0..inner * 100 {
+        if grid.get(x, y) == Some(&Cell::Wall) {
+            grid.set(x, y, Cell::Floor);
+            carved += 1;
+        }
+
+        if carved >= target {
+            break;
+        }
+
+        let (dx, dy) = NEIGHBOURS[seed.range_usize(0, 4)];
+        x = (x as isize + dx).clamp(1, width as isize - 2) as usize;
+        y = (y as isize + dy).clamp(1, height as isize - 2) as usize;
+    }
+
+    grid
+}
+
+/*
+a maze of corridors with the width of one cell
+the rooms of the maze are on odd cells, so odd sizes work best.
+braid (0.0 to 1.0) is the chance that a dead end is opened,
+with 0.0 there is exactly one way between two cells (perfect maze)
+*/
+pub fn maze(width: usize, height: usize, braid: f64, seed: &mut Seed) -> Grid {
+    let mut grid = Grid::new(width, height, Cell::Wall);
+
+    if width < 3 || height < 3 {
+        return grid;
+    }
+
+    let cells_x = (width - 1) / 2;
+    let cells_y = (height - 1) / 2;
+    let to_grid = |cx: usize, cy: usize| (cx * 2 + 1, cy * 2 + 1);
+
+    // recursive backtracker with a stack
+    let mut visited = vec![false; cells_x * cells_y];
+    let mut stack = vec![(0, 0)];
+    visited[0] = true;
+    grid.set(1, 1, Cell::Floor);
+
+    while let Some((cx, cy)) = stack.last().copied() {
+        let unvisited: Vec<(usize, usize)> = maze_neighbours(cx, cy, cells_x, cells_y)
+            .into_iter()
+            .filter(|(nx, ny)| !visited[ny * cells_x + nx])
+            .collect();
+
+        match seed.choose(&unvisited) {
+            Some((nx, ny)) => {
+                let (gx, gy) = to_grid(cx, cy);
+                let (ngx, ngy) = to_grid(*nx, *ny);
+                grid.set((gx + ngx) / 2, (gy + ngy) / 2, Cell::Floor);
+                grid.set(ngx, ngy, Cell::Floor);
+                visited[ny * cells_x + nx] = true;
+                stack.push((*nx, *ny));
+            },
+            None => {
+                stack.pop();
+            },
+        }
+    }
+
+    // open dead ends to create loops
+    for cy in 0..cells_y {
+        for cx in 0..cells_x {
+            let (gx, gy) = to_grid(cx, cy);
+            let neighbours = maze_neighbours(cx, cy, cells_x, cells_y);
+            let open = neighbours
+                .iter()
+                .filter(|(nx, ny)| {
+                    let (ngx, ngy) = to_grid(*nx, *ny);
+                    grid.get((gx + ngx) / 2, (gy + ngy) / 2) == Some(&Cell::Floor)
+                })
+                .count();
+
+            if open != 1 || !seed.chance(braid) {
+                continue;
+            }
+
+            let closed: Vec<(usize, usize)> = neighbours
+                .into_iter()
+                .filter(|(nx, ny)| {
+                    let (ngx, ngy) = to_grid(*nx, *ny);
+                    grid.get((gx + ngx) / 2, (gy + ngy) / 2) == Some(&Cell::Wall)
+                })
+                .collect();
+
+            if let Some((nx, ny)) = seed.choose(&closed) {
+                let (ngx, ngy) = to_grid(*nx, *ny);
+                grid.set((gx + ngx) / 2, (gy + ngy) / 2, Cell::Floor);
+            }
+        }
+    }
+
+    grid
+}
+
+// the neighbours of a maze room
+fn maze_neighbours(cx: usize, cy: usize, cells_x: usize, cells_y: usize) -> Vec<(usize, usize)> {
+    let mut neighbours = vec![];
+
+    for (dx, dy) in NEIGHBOURS {
+        let nx = cx as isize + dx;
+        let ny = cy as isize + dy;
+        if 0 <= nx && nx < cells_x as isize && 0 <= ny && ny < cells_y as isize {
+            neighbours.push((nx as usize, ny as usize));
+        }
+    }
+
+    neighbours
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::math::pathfinding::{astar, Maze};
+    use crate::core::math::pathfinding::hierarchical::Hierarchy;
+
+    // check that the grid has floors, all of them are connected
+    // and the pathfinding finds a way between the first and the last floor
+    fn check(grid: &Grid) {
+        let floors = grid.floors();
+        assert!(floors.len() > 1);
+        assert!(grid.connected());
+
+        let hierarchy = Hierarchy::new(grid, 8);
+        let start = floors[0];
+        let end = floors[floors.len() - 1];
+        assert!(hierarchy.find(grid, start, end).is_some());
+
+        // the border is always a wall
+        for x in 0..grid.width() {
+            assert!(grid.solid(x, 0));
+            assert!(grid.solid(x, grid.height() - 1));
+        }
+    }
+
+    #[test]
+    fn test_bsp_dungeon() {
+        for name in ["a", "b", "c", "d"] {
+            check(&bsp_dungeon(60, 40, 8, &mut Seed::from_str(name)));
+        }
+
+        let a = bsp_dungeon(60, 40, 8, &mut Seed::from_str("same"));
+        let b = bsp_dungeon(60, 40, 8, &mut Seed::from_str("same"));
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_cave() {
+        for name in ["a", "b", "c", "d"] {
+            check(&cave(60, 40, 0.45, 4, &mut Seed::from_str(name)));
+        }
+    }
+
+    #[test]
+    fn test_drunkard_walk() {
+        for name in ["a", "b", "c", "d"] {
+            let grid = drunkard_walk(40, 30, 0.4, &mut Seed::from_str(name));
+            check(&grid);
+            assert_eq!((38 * 28) * 4 / 10, grid.floors().len());
+        }
+    }
+
+    #[test]
+    fn test_maze() {
+        for braid in [0.0, 0.5, 1.0] {
+            check(&maze(41, 31, braid, &mut Seed::from_str("maze")));
+        }
+
+        // a small maze can be solved by the A star algorithm
+        let grid = maze(7, 7, 0.0, &mut Seed::from_str("small"));
+        assert!(astar(&grid, (1, 1), (5, 5)).is_some());
+
+        // a perfect maze has exactly one floor less than cells between its rooms
+        let grid = maze(41, 31, 0.0, &mut Seed::from_str("perfect"));
+        assert_eq!(20 * 15 * 2 - 1, grid.floors().len());
+    }
+}
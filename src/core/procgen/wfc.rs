@@ -0,0 +1,532 @@
+use crate::core::seed::Seed;
+use crate::core::resource::tilemap::TileMap;
+
+// stop searching after this many wrong guesses
+const MAX_BACKTRACKS: usize = 10_000;
+
+// the 4 sides of a tile
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Direction {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+impl Direction {
+    fn all() -> [Direction; 4] {
+        [Direction::Up, Direction::Right, Direction::Down, Direction::Left]
+    }
+
+    fn index(&self) -> usize {
+        match self {
+            Direction::Up => 0,
+            Direction::Right => 1,
+            Direction::Down => 2,
+            Direction::Left => 3,
+        }
+    }
+
+    fn opposite(&self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Right => Direction::Left,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+        }
+    }
+
+    fn offset(&self) -> (isize, isize) {
+        match self {
+            Direction::Up => (0, -1),
+            Direction::Right => (1, 0),
+            Direction::Down => (0, 1),
+            Direction::Left => (-1, 0),
+        }
+    }
+}
+
+/*
+the tiles a generator can use, how often they appear (weight)
+and which tiles are allowed next to each other.
+the names are the names of the tiles in a TileMap
+*/
+#[derive(Clone)]
+pub struct Rules {
+    names: Vec<String>,
+    weights: Vec<f64>,
+    // allowed[a][direction][b] is true if b can be placed in direction of a
+    allowed: Vec<[Vec<bool>; 4]>,
+}
+
+impl Rules {
+    // create rules without any tiles
+    pub fn new() -> Rules {
+        Rules {names: vec![], weights: vec![], allowed: vec![]}
+    }
+
+    // create rules with all tiles of a tilemap and the weight 1.0
+    pub fn from_tilemap(tilemap: &TileMap) -> Rules {
+        let mut rules = Rules::new();
+        for tile in tilemap.as_vec() {
+            rules.add(&tile.name, 1.0);
+        }
+        rules
+    }
+
+    /*
+    learn the rules from an example grid of tile names (rows)
+    every pair of neighbours in the example is allowed,
+    the weight is how often the tile appears in the example
+    */
+    pub fn learn(example: &[Vec<String>]) -> Result<Rules, String> {
+        let mut rules = Rules::new();
+
+        for (y, row) in example.iter().enumerate() {
+            for (x, name) in row.iter().enumerate() {
+                match rules.find(name) {
+                    Some(i) => rules.weights[i] += 1.0,
+                    None => rules.add(name, 1.0),
+                }
+
+                // allowing right and down also allows left and up
+                if let Some(right) = row.get(x + 1) {
+                    rules.add(right, 0.0);
+                    rules.allow(name, Direction::Right, right)?;
+                }
+
+                if let Some(below) = example.get(y + 1).and_then(|row| row.get(x)) {
+                    rules.add(below, 0.0);
+                    rules.allow(name, Direction::Down, below)?;
+                }
+            }
+        }
+
+        Ok(rules)
+    }
+
+    // add a tile, if the tile already exists nothing happens
+    pub fn add(&mut self, name: &str, weight: f64) {
+        if self.find(name).is_some() {
+            return;
+        }
+
+        self.names.push(name.to_string());
+        self.weights.push(weight);
+
+        for allowed in self.allowed.iter_mut() {
+            for direction in allowed.iter_mut() {
+                direction.push(false);
+            }
+        }
+
+        let count = self.names.len();
+        self.allowed.push([vec![false; count], vec![false; count], vec![false; count], vec![false; count]]);
+    }
+
+    // set how often a tile appears compared to the others
+    pub fn set_weight(&mut self, name: &str, weight: f64) -> Result<(), String> {
+        let i = self.index(name)?;
+        self.weights[i] = weight;
+        Ok(())
+    }
+
+    /*
+    allow the tile b in the direction of tile a
+    e.g. allow("grass", Direction::Up, "sky")
+    the opposite (a below b) is allowed as well
+    */
+    pub fn allow(&mut self, a: &str, direction: Direction, b: &str) -> Result<(), String> {
+        let a = self.index(a)?;
+        let b = self.index(b)?;
+        self.allowed[a][direction.index()][b] = true;
+        self.allowed[b][direction.opposite().index()][a] = true;
+        Ok(())
+    }
+
+    // allow a tile next to another tile on all sides
+    pub fn allow_all(&mut self, a: &str, b: &str) -> Result<(), String> {
+        for direction in Direction::all() {
+            self.allow(a, direction, b)?;
+        }
+        Ok(())
+    }
+
+    // returns true if b can be placed in direction of a
+    pub fn allowed(&self, a: &str, direction: Direction, b: &str) -> bool {
+        match (self.find(a), self.find(b)) {
+            (Some(a), Some(b)) => self.allowed[a][direction.index()][b],
+            _ => false,
+        }
+    }
+
+    // the names of all tiles
+    pub fn names(&self) -> Vec<String> {
+        self.names.clone()
+    }
+
+    fn find(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|other| other == name)
+    }
+
+    fn index(&self, name: &str) -> Result<usize, String> {
+        self.find(name).ok_or(format!("Tile '{}' not found in the rules.", name))
+    }
+}
+
+impl Default for Rules {
+    // rules without any tiles
+    fn default() -> Self {
+        Rules::new()
+    }
+}
+
+/*
+Wave function collapse.
+
+creates a grid of tile names (rows) with the size width x height
+where every tile is allowed next to its neighbours.
+the cell with the fewest options is decided first, with a tile chosen
+by weight. when the rules can not be fulfilled the last decision
+is undone and another tile is tried (backtracking).
+the same seed always creates the same grid
+*/
+pub fn generate(rules: &Rules, width: usize, height: usize, seed: &mut Seed) -> Result<Vec<Vec<String>>, String> {
+    let tiles = rules.names.len();
+
+    if tiles == 0 {
+        return Err("Wave function collapse needs at least one tile.".to_string());
+    }
+
+    let mut wave = Wave::new(rules, width, height);
+
+    // remove the tiles that can never appear
+    for cell in 0..width * height {
+        for tile in 0..tiles {
+            if rules.weights[tile] <= 0.0 {
+                wave.ban(cell, tile);
+            }
+        }
+    }
+
+    let all: Vec<usize> = (0..width * height).collect();
+    if !wave.propagate(all) {
+        return Err("The rules can not be fulfilled.".to_string());
+    }
+
+    // the decisions that can be undone, with the length of the trail before them
+    let mut history: Vec<(usize, usize, usize)> = vec![];
+    let mut backtracks = 0;
+
+    while let Some(cell) = wave.lowest_entropy() {
+        // choose a tile of the options by weight
+        let options: Vec<(usize, f64)> = wave.options(cell)
+            .map(|i| (i, rules.weights[i]))
+            .collect();
+
+        let tile = match seed.weighted_choice(&options) {
+            Some(tile) => *tile,
+            None => options[0].0,
+        };
+
+        history.push((wave.trail.len(), cell, tile));
+        wave.collapse(cell, tile);
+
+        if wave.propagate(vec![cell]) {
+            continue;
+        }
+
+        // contradiction, go back and forbid the tile that was chosen
+        loop {
+            backtracks += 1;
+            if backtracks > MAX_BACKTRACKS {
+                return Err("Wave function collapse gave up after too many contradictions.".to_string());
+            }
+
+            let (trail, cell, tile) = match history.pop() {
+                Some(decision) => decision,
+                None => return Err("The rules can not be fulfilled.".to_string()),
+            };
+
+            // the ban belongs to the decision before, so it is undone with it
+            wave.undo(trail);
+            wave.ban(cell, tile);
+
+            if wave.any(cell) && wave.propagate(vec![cell]) {
+                break;
+            }
+        }
+    }
+
+    let mut grid = vec![];
+    for y in 0..height {
+        let mut row = vec![];
+        for x in 0..width {
+            let tile = wave.options(y * width + x).next().unwrap_or(0);
+            row.push(rules.names[tile].clone());
+        }
+        grid.push(row);
+    }
+
+    Ok(grid)
+}
+
+/*
+the possible tiles of every cell as one bitset per cell,
+every removed option is kept in the trail so backtracking
+can undo it without copying the whole wave
+*/
+struct Wave {
+    width: usize,
+    height: usize,
+    tiles: usize,
+    words: usize, // the words of a bitset
+    bits: Vec<u64>,
+    // compatible[a * 4 + direction] has the tiles that can be placed in direction of a
+    compatible: Vec<Vec<u64>>,
+    trail: Vec<(usize, usize)>,
+}
+
+impl Wave {
+    // a wave where every cell can be any tile
+    fn new(rules: &Rules, width: usize, height: usize) -> Self {
+        let tiles = rules.names.len();
+        let words = tiles.div_ceil(64);
+
+        let mut full = vec![0u64; words];
+        for tile in 0..tiles {
+            full[tile / 64] |= 1 << (tile % 64);
+        }
+
+        let mut compatible = vec![];
+        for allowed in rules.allowed.iter() {
+            for direction in allowed.iter() {
+                let mut set = vec![0u64; words];
+                for (b, allowed) in direction.iter().enumerate() {
+                    if *allowed {
+                        set[b / 64] |= 1 << (b % 64);
+                    }
+                }
+                compatible.push(set);
+            }
+        }
+
+        Wave {
+            width, height, tiles, words,
+            bits: full.repeat(width * height),
+            compatible,
+            trail: vec![],
+        }
+    }
+
+    fn cell(&self, cell: usize) -> &[u64] {
+        &self.bits[cell * self.words..(cell + 1) * self.words]
+    }
+
+    fn has(&self, cell: usize, tile: usize) -> bool {
+        self.cell(cell)[tile / 64] & (1 << (tile % 64)) != 0
+    }
+
+    fn any(&self, cell: usize) -> bool {
+        self.cell(cell).iter().any(|word| *word != 0)
+    }
+
+    fn count(&self, cell: usize) -> usize {
+        self.cell(cell).iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    // the tiles that are still possible in a cell
+    fn options(&self, cell: usize) -> impl Iterator<Item = usize> + '_ {
+        (0..self.tiles).filter(move |tile| self.has(cell, *tile))
+    }
+
+    // remove an option and remember it for undo
+    fn ban(&mut self, cell: usize, tile: usize) {
+        if self.has(cell, tile) {
+            self.bits[cell * self.words + tile / 64] &= !(1 << (tile % 64));
+            self.trail.push((cell, tile));
+        }
+    }
+
+    // bring back every option removed after the trail had the given length
+    fn undo(&mut self, length: usize) {
+        while self.trail.len() > length {
+            if let Some((cell, tile)) = self.trail.pop() {
+                self.bits[cell * self.words + tile / 64] |= 1 << (tile % 64);
+            }
+        }
+    }
+
+    // the undecided cell with the fewest options
+    fn lowest_entropy(&self) -> Option<usize> {
+        let mut lowest: Option<(usize, usize)> = None;
+
+        for i in 0..self.width * self.height {
+            let count = self.count(i);
+            if count > 1 && lowest.is_none_or(|(_, lowest_count)| count < lowest_count) {
+                lowest = Some((i, count));
+            }
+        }
+
+        lowest.map(|(i, _)| i)
+    }
+
+    fn collapse(&mut self, cell: usize, tile: usize) {
+        for i in 0..self.tiles {
+            if i != tile {
+                self.ban(cell, i);
+            }
+        }
+    }
+
+    /*
+    remove the options of the neighbours that are not allowed anymore
+    returns false if a cell has no options left
+    */
+    fn propagate(&mut self, changed: Vec<usize>) -> bool {
+        let mut stack = changed;
+
+        while let Some(cell) = stack.pop() {
+            let (x, y) = ((cell % self.width) as isize, (cell / self.width) as isize);
+
+            for direction in Direction::all() {
+                let (dx, dy) = direction.offset();
+                let (nx, ny) = (x + dx, y + dy);
+                if nx < 0 || ny < 0 || nx >= self.width as isize || ny >= self.height as isize {
+                    continue;
+                }
+
+                let neighbour = ny as usize * self.width + nx as usize;
+
+                // b stays if any option of the cell allows it
+                let mut supported = vec![0u64; self.words];
+                for a in self.options(cell) {
+                    for (word, allowed) in supported.iter_mut().zip(self.compatible[a * 4 + direction.index()].iter()) {
+                        *word |= allowed;
+                    }
+                }
+
+                let unsupported: Vec<usize> = self.options(neighbour)
+                    .filter(|b| supported[b / 64] & (1 << (b % 64)) == 0)
+                    .collect();
+
+                if !unsupported.is_empty() {
+                    for b in unsupported {
+                        self.ban(neighbour, b);
+                    }
+                    if !self.any(neighbour) {
+                        return false;
+                    }
+                    stack.push(neighbour);
+                }
+            }
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // checks that every neighbour in the grid is allowed
+    fn valid(rules: &Rules, grid: &[Vec<String>]) -> bool {
+        for (y, row) in grid.iter().enumerate() {
+            for (x, name) in row.iter().enumerate() {
+                if let Some(right) = row.get(x + 1) {
+                    if !rules.allowed(name, Direction::Right, right) {
+                        return false;
+                    }
+                }
+
+                if let Some(below) = grid.get(y + 1).map(|row| &row[x]) {
+                    if !rules.allowed(name, Direction::Down, below) {
+                        return false;
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    // water next to sand, sand next to grass, but never water next to grass
+    fn coast() -> Rules {
+        let mut rules = Rules::new();
+        rules.add("water", 2.0);
+        rules.add("sand", 1.0);
+        rules.add("grass", 2.0);
+        rules.allow_all("water", "water").unwrap();
+        rules.allow_all("water", "sand").unwrap();
+        rules.allow_all("sand", "sand").unwrap();
+        rules.allow_all("sand", "grass").unwrap();
+        rules.allow_all("grass", "grass").unwrap();
+        rules
+    }
+
+    #[test]
+    fn test_generate() {
+        let rules = coast();
+        let grid = generate(&rules, 20, 15, &mut Seed::from_str("coast")).unwrap();
+        assert_eq!(15, grid.len());
+        assert_eq!(20, grid[0].len());
+        assert!(valid(&rules, &grid));
+
+        let again = generate(&rules, 20, 15, &mut Seed::from_str("coast")).unwrap();
+        assert_eq!(grid, again);
+    }
+
+    #[test]
+    fn test_learn() {
+        let example: Vec<Vec<String>> = [
+            "sky sky sky",
+            "sky sky sky",
+            "grass grass grass",
+            "dirt dirt dirt",
+        ].iter().map(|row| row.split(' ').map(|name| name.to_string()).collect()).collect();
+
+        let rules = Rules::learn(&example).unwrap();
+        assert!(rules.allowed("sky", Direction::Down, "grass"));
+        assert!(rules.allowed("grass", Direction::Up, "sky"));
+        assert!(!rules.allowed("sky", Direction::Down, "dirt"));
+        assert!(!rules.allowed("grass", Direction::Down, "sky"));
+
+        let grid = generate(&rules, 6, 4, &mut Seed::from_str("learn")).unwrap();
+        assert!(valid(&rules, &grid));
+    }
+
+    #[test]
+    fn test_undo() {
+        // more tiles than fit in one word of the bitset
+        let mut rules = Rules::new();
+        for i in 0..70 {
+            rules.add(&format!("tile {}", i), 1.0);
+        }
+        rules.allow_all("tile 0", "tile 69").unwrap();
+
+        let mut wave = Wave::new(&rules, 2, 1);
+        assert_eq!(70, wave.count(1));
+
+        wave.collapse(1, 69);
+        assert_eq!(vec![69], wave.options(1).collect::<Vec<usize>>());
+        assert!(wave.propagate(vec![1]));
+        assert_eq!(vec![0], wave.options(0).collect::<Vec<usize>>());
+
+        wave.undo(0);
+        assert_eq!(70, wave.count(0));
+        assert_eq!(70, wave.count(1));
+        assert!(wave.trail.is_empty());
+    }
+
+    #[test]
+    fn test_contradiction() {
+        // a tile that can not have any neighbour
+        let mut rules = Rules::new();
+        rules.add("alone", 1.0);
+        assert!(generate(&rules, 2, 1, &mut Seed::from_str("a")).is_err());
+        assert!(generate(&rules, 1, 1, &mut Seed::from_str("a")).is_ok());
+
+        assert!(rules.allow("alone", Direction::Up, "missing").is_err());
+        assert!(generate(&Rules::new(), 2, 2, &mut Seed::from_str("a")).is_err());
+    }
+}
@@ -2,6 +2,7 @@ use std::time::Instant;
 use crate::core::color::Color;
 use crate::core::window::Window;
 use crate::core::mouse::Mouse;
+use crate::core::shader::post::PostProcess;
 
 /*
 used as argument in the main init function
@@ -44,12 +45,18 @@ pub trait Runtime {
 
     // update is called every frame
     fn update(&mut self, draw: &Draw);
+
+    // return a post processing chain to apply it to every frame
+    fn post_process(&mut self) -> Option<&mut PostProcess> {
+        None
+    }
 }
 
 /*
 holds important components for the draw functions,
 like the shaderprograms, camera, events etc.
 */
+#[derive(Clone)]
 pub struct Draw {
     pub performance: Performance,
     pub window: Window,
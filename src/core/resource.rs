@@ -1,3 +1,4 @@
 pub mod font;
 pub mod image;
-pub mod tilemap;
\ No newline at end of file
+pub mod tilemap;
+pub mod animation;
\ No newline at end of file
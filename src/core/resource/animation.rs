@@ -0,0 +1,247 @@
+use crate::core::shader::object::TextureCoordinate;
+use crate::core::resource::tilemap::{TileMap, TileDataRotation};
+use crate::core::file;
+
+// how a clip continues after its last frame
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PlayMode {
+    // start again with the first frame
+    Loop,
+    // play backwards to the first frame and forwards again
+    PingPong,
+    // stop on the last frame
+    Once,
+}
+
+impl PlayMode {
+    fn parse(mode: &str) -> Result<PlayMode, String> {
+        match mode {
+            "loop" => Ok(PlayMode::Loop),
+            "pingpong" => Ok(PlayMode::PingPong),
+            "once" => Ok(PlayMode::Once),
+            _ => Err(format!("Unknown play mode '{}'.", mode)),
+        }
+    }
+}
+
+// one frame of a clip, the name is the name of the tile in the tilemap
+#[derive(Clone, Debug)]
+pub struct Frame {
+    name: String,
+    texcoord: TextureCoordinate,
+    // seconds the frame is shown
+    duration: f32,
+    // an event that is fired when the frame is shown, e.g. a footstep
+    event: Option<String>,
+}
+
+impl Frame {
+    // the duration is checked when the frame is added to a clip
+    pub fn new(name: &str, texcoord: TextureCoordinate, duration: f32, event: Option<&str>) -> Frame {
+        Frame {
+            name: name.to_string(),
+            texcoord,
+            duration,
+            event: event.map(|event| event.to_string()),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn texcoord(&self) -> TextureCoordinate {
+        self.texcoord
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.duration
+    }
+
+    pub fn event(&self) -> Option<&str> {
+        self.event.as_deref()
+    }
+}
+
+/*
+a named list of frames, it can only be created with new
+so every clip has frames and every frame a duration
+*/
+#[derive(Clone, Debug)]
+pub struct Clip {
+    name: String,
+    mode: PlayMode,
+    frames: Vec<Frame>,
+}
+
+impl Clip {
+    // a clip needs at least one frame and every frame a duration above 0
+    pub fn new(name: &str, mode: PlayMode, frames: Vec<Frame>) -> Result<Clip, String> {
+        if frames.is_empty() {
+            return Err(format!("The clip '{}' has no frames.", name));
+        }
+
+        if frames.iter().any(|frame| frame.duration <= 0.0 || frame.duration.is_nan()) {
+            return Err(format!("Every frame of the clip '{}' needs a duration above 0.", name));
+        }
+
+        Ok(Clip {name: name.to_string(), mode, frames})
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn mode(&self) -> PlayMode {
+        self.mode
+    }
+
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    // the seconds to play every frame once
+    pub fn duration(&self) -> f32 {
+        self.frames.iter().map(|frame| frame.duration).sum()
+    }
+}
+
+/*
+holds the clips of an animation file
+
+the file is a companion of the tilemap file, every line is one clip:
+name,mode,frame:duration,frame:duration:event,...
+e.g.
+walk,loop,walk_0:0.1,walk_1:0.1:step,walk_2:0.1,walk_3:0.1:step
+attack,once,attack_0:0.05,attack_1:0.1:hit,attack_2:0.2
+
+mode is loop, pingpong or once and the frames
+are the names of the tiles in the tilemap
+*/
+#[derive(Clone)]
+pub struct Animation {
+    clips: Vec<Clip>,
+}
+
+impl Animation {
+    // load the animation file using the tiles of the tilemap
+    pub fn new(path: &str, tilemap: &TileMap) -> Result<Animation, String> {
+        let file = file::read(path)?;
+        Animation::parse(&file, tilemap)
+    }
+
+    // create the clips from the content of an animation file
+    pub fn parse(text: &str, tilemap: &TileMap) -> Result<Animation, String> {
+        let mut animation = Animation {clips: vec![]};
+
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let clip = Animation::parse_line(line, tilemap)?;
+            animation.add(clip);
+        }
+
+        Ok(animation)
+    }
+
+    // add a clip, a clip with the same name is replaced
+    pub fn add(&mut self, clip: Clip) {
+        match self.clips.iter().position(|other| other.name == clip.name) {
+            Some(i) => self.clips[i] = clip,
+            None => self.clips.push(clip),
+        }
+    }
+
+    // get a copy of a clip
+    pub fn get(&self, name: &str) -> Result<Clip, String> {
+        for clip in &self.clips {
+            if clip.name == name {
+                return Ok(clip.clone());
+            }
+        }
+
+        Err(format!("Could not get clip '{}'.", name))
+    }
+
+    // the names of all clips
+    pub fn names(&self) -> Vec<String> {
+        self.clips.iter().map(|clip| clip.name.clone()).collect()
+    }
+
+    fn parse_line(line: &str, tilemap: &TileMap) -> Result<Clip, String> {
+        let parts: Vec<&str> = line.split(',').map(|part| part.trim()).collect();
+
+        if parts.len() < 3 {
+            return Err(format!("Could not parse line '{}'", line));
+        }
+
+        let mode = PlayMode::parse(parts[1])?;
+        let mut frames = vec![];
+
+        for part in parts[2..].iter() {
+            let values: Vec<&str> = part.split(':').collect();
+            if values.len() < 2 || values.len() > 3 {
+                return Err(format!("Could not parse frame '{}' in line '{}'", part, line));
+            }
+
+            let tile = tilemap.get(values[0])?;
+            let duration = values[1]
+                .parse::<f32>()
+                .map_err(|_| format!("Could not parse the duration of frame '{}'", part))?;
+
+            frames.push(Frame::new(&tile.name, tile.texcoord(&TileDataRotation::Bottom), duration, values.get(2).copied()));
+        }
+
+        Clip::new(parts[0], mode, frames)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::resource::image::Image;
+
+    // a tilemap with the tiles a_0 to a_3 in one row
+    fn tilemap() -> TileMap {
+        let path = std::env::temp_dir().join("d7engine_animation_test.tilemap");
+        let path = path.to_str().unwrap();
+        file::write(path, "a_0,0,0\na_1,16,0\na_2,32,0\na_3,48,0").unwrap();
+        let image = Image::from(image::RgbaImage::new(64, 16));
+        TileMap::new(path, image, 16).unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        let tilemap = tilemap();
+        let text = "walk,loop,a_0:0.1,a_1:0.1:step\n\nidle, pingpong, a_2:0.5, a_3:0.25\n";
+        let animation = Animation::parse(text, &tilemap).unwrap();
+
+        assert_eq!(vec!["walk".to_string(), "idle".to_string()], animation.names());
+        let walk = animation.get("walk").unwrap();
+        assert_eq!(PlayMode::Loop, walk.mode());
+        assert_eq!(Some("step"), walk.frames()[1].event());
+        assert_eq!(tilemap.get("a_1").unwrap().texcoord(&TileDataRotation::Bottom), walk.frames()[1].texcoord());
+        assert!((animation.get("idle").unwrap().duration() - 0.75).abs() < 0.0001);
+
+        assert!(animation.get("run").is_err());
+        assert!(Animation::parse("walk,loop,b_0:0.1", &tilemap).is_err());
+        assert!(Animation::parse("walk,forever,a_0:0.1", &tilemap).is_err());
+        assert!(Animation::parse("walk,loop,a_0:0", &tilemap).is_err());
+        assert!(Animation::parse("walk,loop", &tilemap).is_err());
+    }
+    #[test]
+    fn test_clip() {
+        let frame = |duration| Frame::new("a_0", [0.0; 8], duration, None);
+        assert!(Clip::new("empty", PlayMode::Loop, vec![]).is_err());
+        assert!(Clip::new("zero", PlayMode::Loop, vec![frame(0.1), frame(0.0)]).is_err());
+        assert!(Clip::new("negative", PlayMode::Once, vec![frame(-1.0)]).is_err());
+        assert!(Clip::new("nan", PlayMode::PingPong, vec![frame(f32::NAN)]).is_err());
+
+        let clip = Clip::new("walk", PlayMode::Loop, vec![frame(0.1), frame(0.2)]).unwrap();
+        assert_eq!("walk", clip.name());
+        assert_eq!(2, clip.frames().len());
+    }
+}
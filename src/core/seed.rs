@@ -2,16 +2,18 @@ use std::time::{SystemTime, UNIX_EPOCH};
 use sha2::{Sha512, Digest};
 
 /*
-seed can get the bytes from a string
-and give you certain RANDOM information like a random bool
+seed creates deterministic RANDOM information from a string
+like a random bool, numbers in a range or a shuffled array
 
-it stores the bytes the index of the bytes vector 
-and the current position bit
+the string is hashed with sha512, the hash seeds a xoshiro256**
+generator. the state can be saved and loaded for save games
+and fork creates independent generators for single systems
 */
 pub struct Seed {
-    bytes: Vec<u8>,
-    index: usize,
-    bit: u8,
+    root: Vec<u8>,
+    state: [u64; 4],
+    bits: u64,
+    bits_left: u32,
 }
 
 /*
@@ -24,12 +26,9 @@ pub enum Roll {
 }
 
 impl Seed {
-    // create a seed object from a string, sets default index and bit indizes
+    // create a seed object from a string
     pub fn from_str(seed: &str) -> Seed {
-        let bytes = Seed::hash(seed);
-        let index = 0;
-        let bit = 0;
-        Seed {bytes, index, bit}
+        Seed::from_root(Seed::hash(seed.as_bytes()))
     }
 
     // create a seed object from the current timestamp
@@ -42,26 +41,70 @@ impl Seed {
     }
  
     /*
-    check if the next bit in the system is a 1 
-    then move to the next bit and or index in the byte vector
+    create an independent generator for a name
+    e.g. seed.fork("loot") so random calls in one system
+    do not change the numbers of another system.
+    the fork only depends on the seed string and the name,
+    not on how many numbers were already generated
     */
-    pub fn next_bool(&mut self) -> bool {
-        if 7 < self.bit {
-            self.bit = 0;
-            self.index += 1;
-        } 
+    pub fn fork(&self, name: &str) -> Seed {
+        let mut data = self.root.clone();
+        data.push(0);
+        data.extend_from_slice(name.as_bytes());
+        Seed::from_root(Seed::hash(&data))
+    }
+
+    /*
+    save the current state of the generator as a string
+    load it again with Seed::from_state
+    */
+    pub fn state(&self) -> String {
+        let root: String = self.root.iter().map(|byte| format!("{:02x}", byte)).collect();
+        let state: Vec<String> = self.state.iter().map(|word| format!("{:016x}", word)).collect();
+        format!("{},{},{:016x},{}", root, state.join(","), self.bits, self.bits_left)
+    }
+
+    // restore a generator from a string created by Seed::state
+    pub fn from_state(state: &str) -> Result<Seed, String> {
+        let error = || format!("Could not parse seed state '{}'.", state);
+        let parts: Vec<&str> = state.split(',').collect();
 
-        if self.bytes.len() - 1 < self.index {
-            self.index = 0;
-            self.next_hash_iteration();
+        if parts.len() != 7 || parts[0].len() != 128 {
+            return Err(error());
         }
 
-        let byte = self.bytes[self.index];
-        let shifted = byte >> self.bit;
-        let new_byte = shifted & 1;
+        let mut root = vec![];
+        for i in (0..128).step_by(2) {
+            let byte = parts[0].get(i..i + 2).ok_or_else(error)?;
+            root.push(u8::from_str_radix(byte, 16).map_err(|_| error())?);
+        }
+
+        let mut words = [0u64; 4];
+        for (i, word) in words.iter_mut().enumerate() {
+            *word = u64::from_str_radix(parts[i + 1], 16).map_err(|_| error())?;
+        }
 
-        self.bit += 1;
-        new_byte == 1
+        let bits = u64::from_str_radix(parts[5], 16).map_err(|_| error())?;
+        let bits_left = parts[6].parse::<u32>().map_err(|_| error())?;
+
+        if words == [0; 4] || bits_left > 64 {
+            return Err(error());
+        }
+
+        Ok(Seed {root, state: words, bits, bits_left})
+    }
+
+    // get the next random bit as a bool
+    pub fn next_bool(&mut self) -> bool {
+        if self.bits_left == 0 {
+            self.bits = self.next_u64();
+            self.bits_left = 64;
+        }
+
+        let bit = self.bits & 1;
+        self.bits >>= 1;
+        self.bits_left -= 1;
+        bit == 1
     }
 
     /*
@@ -69,20 +112,7 @@ impl Seed {
     from 0 to 255
     */
     pub fn next_u8(&mut self) -> u8 {
-        let mut num: u8 = 0;
-        
-        for _ in 0..8 {
-            // left shift by one place (00010) => (00100)
-            num = num << 1;
-
-            num = if self.next_bool() {
-                num | 1
-            } else {
-                num | 0
-            };
-        } 
-
-        num
+        (self.next_u64() >> 56) as u8
     }
 
     /*
@@ -100,34 +130,429 @@ impl Seed {
 
     /*
     get a random index from an array
-    this will only work with an array length
-    up to 255
+    every index has the same chance,
+    an empty array has no index, so None is returned
     */
-    pub fn roll_index<ElementType>(&mut self, elements: &Vec<ElementType>) -> usize {
-        let delimiter = 256.0 / elements.len() as f32;
-        let roll = self.next_u8();
-        let index = roll as f32 / delimiter;
-        index.floor() as usize
+    pub fn roll_index<ElementType>(&mut self, elements: &[ElementType]) -> Option<usize> {
+        if elements.is_empty() {
+            return None;
+        }
+
+        Some(self.range_usize(0, elements.len()))
+    }
+
+    // get the next 32 bits in the seed as an u32
+    pub fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    // get the next 64 bits in the seed as an u64 (xoshiro256**)
+    pub fn next_u64(&mut self) -> u64 {
+        let s = &mut self.state;
+        let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 17;
+
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+
+        result
+    }
+
+    /*
+    get a number from min (included) to max (excluded)
+    if max is not greater than min, min is returned
+    */
+    pub fn range_u32(&mut self, min: u32, max: u32) -> u32 {
+        if max <= min {
+            return min;
+        }
+
+        min + self.below((max - min) as u64) as u32
+    }
+
+    // get a number from min (included) to max (excluded)
+    pub fn range_i64(&mut self, min: i64, max: i64) -> i64 {
+        if max <= min {
+            return min;
+        }
+
+        let span = max.wrapping_sub(min) as u64;
+        min.wrapping_add(self.below(span) as i64)
+    }
+
+    // get a number from min (included) to max (excluded)
+    pub fn range_usize(&mut self, min: usize, max: usize) -> usize {
+        if max <= min {
+            return min;
+        }
+
+        min + self.below((max - min) as u64) as usize
+    }
+
+    // get a float from 0.0 (included) to 1.0 (excluded)
+    pub fn next_f32(&mut self) -> f32 {
+        // 24 bits fit exactly into the mantissa of a f32
+        (self.next_u32() >> 8) as f32 / (1u32 << 24) as f32
+    }
+
+    // get a float from 0.0 (included) to 1.0 (excluded)
+    pub fn next_f64(&mut self) -> f64 {
+        // 53 bits fit exactly into the mantissa of a f64
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    // get a float from min (included) to max (excluded)
+    pub fn range_f32(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next_f32()
+    }
+
+    // get a float from min (included) to max (excluded)
+    pub fn range_f64(&mut self, min: f64, max: f64) -> f64 {
+        min + (max - min) * self.next_f64()
+    }
+
+    // returns true with the probability 0.0 to 1.0
+    pub fn chance(&mut self, probability: f64) -> bool {
+        self.next_f64() < probability
+    }
+
+    /*
+    a normal distributed number (box muller transform)
+    around mean with the standard deviation std_dev
+    */
+    pub fn gaussian(&mut self, mean: f64, std_dev: f64) -> f64 {
+        // 1.0 - x so the logarithm never gets 0.0
+        let u1 = 1.0 - self.next_f64();
+        let u2 = self.next_f64();
+        let z = (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos();
+        mean + z * std_dev
     }
 
-    // hash a string and return it as a vector of u8
-    fn hash(seed: &str) -> Vec<u8> {
+    /*
+    an exponential distributed number
+    useful for the time between random events that happen
+    rate times per second on average
+    */
+    pub fn exponential(&mut self, rate: f64) -> f64 {
+        -(1.0 - self.next_f64()).ln() / rate
+    }
+
+    // shuffle the elements of an array (fisher yates)
+    pub fn shuffle<ElementType>(&mut self, elements: &mut [ElementType]) {
+        for i in (1..elements.len()).rev() {
+            let j = self.range_usize(0, i + 1);
+            elements.swap(i, j);
+        }
+    }
+
+    // get a random element of an array, None if the array is empty
+    pub fn choose<'a, ElementType>(&mut self, elements: &'a [ElementType]) -> Option<&'a ElementType> {
+        if elements.is_empty() {
+            return None;
+        }
+
+        let i = self.range_usize(0, elements.len());
+        elements.get(i)
+    }
+
+    /*
+    get a random element where every element has a weight,
+    an element with the weight 2.0 is chosen twice as often
+    as one with 1.0. weights below or equal 0.0 and NaN are never chosen
+    */
+    pub fn weighted_choice<'a, ElementType>(&mut self, elements: &'a [(ElementType, f64)]) -> Option<&'a ElementType> {
+        let total: f64 = elements
+            .iter()
+            .filter(|(_, weight)| chosen(*weight))
+            .map(|(_, weight)| weight)
+            .sum();
+
+        if total <= 0.0 {
+            return None;
+        }
+
+        let mut roll = self.next_f64() * total;
+        let mut last = None;
+
+        for (element, weight) in elements.iter() {
+            if !chosen(*weight) {
+                continue;
+            }
+
+            if roll < *weight {
+                return Some(element);
+            }
+
+            roll -= weight;
+            last = Some(element);
+        }
+
+        // only reached through float rounding
+        last
+    }
+
+    /*
+    get amount different elements of an array
+    if amount is greater than the array all elements are returned
+    */
+    pub fn sample_without_replacement<'a, ElementType>(&mut self, elements: &'a [ElementType], amount: usize) -> Vec<&'a ElementType> {
+        let amount = amount.min(elements.len());
+        let mut indices: Vec<usize> = (0..elements.len()).collect();
+
+        // a partial fisher yates shuffle
+        for i in 0..amount {
+            let j = self.range_usize(i, indices.len());
+            indices.swap(i, j);
+        }
+
+        indices[..amount].iter().map(|i| &elements[*i]).collect()
+    }
+
+    // get an unbiased number from 0 to span (excluded)
+    fn below(&mut self, span: u64) -> u64 {
+        // reject the numbers in the last incomplete
+        // block of span, otherwise small numbers are more likely
+        let zone = u64::MAX - (u64::MAX - span + 1) % span;
+
+        loop {
+            let num = self.next_u64();
+            if num <= zone {
+                return num % span;
+            }
+        }
+    }
+
+    // create the generator from the sha512 hash of the seed
+    fn from_root(root: Vec<u8>) -> Seed {
+        let mut state = [0u64; 4];
+        for (i, word) in state.iter_mut().enumerate() {
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(&root[i * 8..i * 8 + 8]);
+            *word = u64::from_le_bytes(bytes);
+        }
+
+        // xoshiro does not work with a state of only zeros
+        if state == [0; 4] {
+            state[0] = 1;
+        }
+
+        Seed {root, state, bits: 0, bits_left: 0}
+    }
+
+    // hash some bytes and return it as a vector of u8
+    fn hash(data: &[u8]) -> Vec<u8> {
         let mut hasher = Sha512::new();
-        hasher.update(seed);
+        hasher.update(data);
         hasher.finalize().to_vec()
     }
+}
 
-    /* 
-    take the hash and hash it, 
-    this way we have unlimited different randomness
-    */
-    fn next_hash_iteration(&mut self) {
-        let mut iteration = String::from("");
+// true if an element with the weight can be chosen, NaN is not above 0
+fn chosen(weight: f64) -> bool {
+    weight > 0.0
+}
 
-        for byte in &self.bytes {
-            iteration.push_str(&byte.to_string());
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the chi squared value of counted buckets that should all have the same size
+    fn chi_squared(buckets: &[usize], samples: usize) -> f64 {
+        let expected = samples as f64 / buckets.len() as f64;
+        buckets
+            .iter()
+            .map(|count| (*count as f64 - expected).powi(2) / expected)
+            .sum()
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let mut a = Seed::from_str("d7engine");
+        let mut b = Seed::from_str("d7engine");
+        let mut c = Seed::from_str("d7engine2");
+
+        let a: Vec<u32> = (0..100).map(|_| a.next_u32()).collect();
+        let b: Vec<u32> = (0..100).map(|_| b.next_u32()).collect();
+        let c: Vec<u32> = (0..100).map(|_| c.next_u32()).collect();
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn test_ranges() {
+        let mut seed = Seed::from_str("ranges");
+
+        for _ in 0..1000 {
+            let num = seed.range_u32(10, 20);
+            assert!((10..20).contains(&num));
+
+            let num = seed.range_i64(-5, 5);
+            assert!((-5..5).contains(&num));
+
+            let num = seed.range_f32(-1.0, 1.0);
+            assert!((-1.0..1.0).contains(&num));
+
+            let num = seed.next_f64();
+            assert!((0.0..1.0).contains(&num));
         }
 
-        self.bytes = Seed::hash(&iteration);
-    } 
-}
\ No newline at end of file
+        assert_eq!(3, seed.range_usize(3, 3));
+        assert_eq!(i64::MIN, seed.range_i64(i64::MIN, i64::MIN));
+        let num = seed.range_i64(i64::MIN, i64::MAX);
+        assert!(num < i64::MAX);
+    }
+
+    #[test]
+    fn test_uniform() {
+        let mut seed = Seed::from_str("uniform");
+        let samples = 20_000;
+
+        // 10 buckets, 9 degrees of freedom,
+        // 27.88 is the critical value for p = 0.001
+        let mut buckets = vec![0; 10];
+        for _ in 0..samples {
+            buckets[seed.range_usize(0, 10)] += 1;
+        }
+        assert!(chi_squared(&buckets, samples) < 27.88);
+
+        let mut buckets = vec![0; 10];
+        for _ in 0..samples {
+            buckets[(seed.next_f64() * 10.0) as usize] += 1;
+        }
+        assert!(chi_squared(&buckets, samples) < 27.88);
+
+        // roll index works for arrays longer than 255
+        let elements = vec![0; 1000];
+        let mut high = false;
+        for _ in 0..1000 {
+            let i = seed.roll_index(&elements).unwrap();
+            assert!(i < 1000);
+            high |= i >= 256;
+        }
+        assert!(high);
+
+        let empty: Vec<u8> = vec![];
+        assert_eq!(None, seed.roll_index(&empty));
+    }
+
+    #[test]
+    fn test_gaussian() {
+        let mut seed = Seed::from_str("gaussian");
+        let samples = 20_000;
+        let values: Vec<f64> = (0..samples).map(|_| seed.gaussian(5.0, 2.0)).collect();
+
+        let mean = values.iter().sum::<f64>() / samples as f64;
+        let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / samples as f64;
+        assert!((mean - 5.0).abs() < 0.1);
+        assert!((variance.sqrt() - 2.0).abs() < 0.1);
+
+        let mean = (0..samples).map(|_| seed.exponential(4.0)).sum::<f64>() / samples as f64;
+        assert!((mean - 0.25).abs() < 0.02);
+    }
+
+    #[test]
+    fn test_shuffle() {
+        let mut seed = Seed::from_str("shuffle");
+        let samples = 10_000;
+
+        // every element should end up on every position equally often
+        let mut buckets = vec![0; 5];
+        for _ in 0..samples {
+            let mut elements = [0, 1, 2, 3, 4];
+            seed.shuffle(&mut elements);
+            buckets[elements[0]] += 1;
+
+            let mut sorted = elements.to_vec();
+            sorted.sort();
+            assert_eq!(vec![0, 1, 2, 3, 4], sorted);
+        }
+
+        // 4 degrees of freedom, p = 0.001
+        assert!(chi_squared(&buckets, samples) < 18.47);
+    }
+
+    #[test]
+    fn test_choice() {
+        let mut seed = Seed::from_str("choice");
+        let empty: [u8; 0] = [];
+        assert_eq!(None, seed.choose(&empty));
+        assert_eq!(Some(&7), seed.choose(&[7]));
+
+        let elements = [("a", 1.0), ("n", f64::NAN), ("b", 3.0), ("c", 0.0), ("d", -1.0)];
+        let mut b = 0;
+        for _ in 0..10_000 {
+            match seed.weighted_choice(&elements) {
+                Some(&"a") => (),
+                Some(&"b") => b += 1,
+                _ => panic!("element with a weight of 0 or less was chosen"),
+            }
+        }
+        assert!((7300..7700).contains(&b));
+
+        let weightless = [("a", 0.0), ("n", f64::NAN)];
+        assert_eq!(None, seed.weighted_choice(&weightless));
+
+        let elements = [1, 2, 3, 4, 5, 6];
+        let mut sample = seed.sample_without_replacement(&elements, 4);
+        assert_eq!(4, sample.len());
+        sample.sort();
+        sample.dedup();
+        assert_eq!(4, sample.len());
+        assert_eq!(6, seed.sample_without_replacement(&elements, 10).len());
+    }
+
+    #[test]
+    fn test_golden_values() {
+        // these values must never change, on any platform,
+        // otherwise worlds created from a seed look different
+        let mut seed = Seed::from_str("d7engine");
+        let values: Vec<u64> = (0..4).map(|_| seed.next_u64()).collect();
+        assert_eq!(vec![3620691594758204463, 5848906736328683804, 3994845483257645781, 10312566999539431779], values);
+
+        let mut seed = Seed::from_str("d7engine").fork("loot");
+        assert_eq!(15530668544594653816, seed.next_u64());
+
+        let mut seed = Seed::from_str("");
+        assert_eq!(3383186955, seed.next_u32());
+        assert_eq!(39, seed.range_usize(0, 100));
+    }
+
+    #[test]
+    fn test_state() {
+        let mut seed = Seed::from_str("save game");
+        seed.next_u64();
+        seed.next_bool();
+
+        let mut loaded = Seed::from_state(&seed.state()).unwrap();
+        for _ in 0..100 {
+            assert_eq!(seed.next_bool(), loaded.next_bool());
+            assert_eq!(seed.next_u64(), loaded.next_u64());
+        }
+
+        // forks still work after loading
+        assert_eq!(seed.fork("a").next_u64(), loaded.fork("a").next_u64());
+
+        assert!(Seed::from_state("").is_err());
+        assert!(Seed::from_state("1,2,3").is_err());
+    }
+
+    #[test]
+    fn test_fork() {
+        let mut a = Seed::from_str("world");
+        let b = Seed::from_str("world");
+
+        // an extra random call in one system
+        // does not change the numbers of the fork
+        a.next_u64();
+        let mut loot_a = a.fork("loot");
+        let mut loot_b = b.fork("loot");
+        assert_eq!(loot_a.next_u64(), loot_b.next_u64());
+
+        assert_ne!(b.fork("loot").next_u64(), b.fork("enemies").next_u64());
+        assert_ne!(b.fork("loot").next_u64(), b.fork("loot").fork("loot").next_u64());
+    }
+}
@@ -1,8 +1,15 @@
 pub mod program;
+pub mod cache;
 pub mod object;
 pub mod data;
 pub mod shader;
 pub mod instanced;
+pub mod batch;
+pub mod target;
+pub mod uniform;
+pub mod post;
+pub mod material;
+pub mod watch;
 
 use gl::types::*;
 use std::ffi::CString;
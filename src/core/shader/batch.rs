@@ -0,0 +1,574 @@
+use std::rc::Rc;
+use std::collections::HashMap;
+use gl::types::*;
+use crate::core::shader::object::{VertexArray, Buffer, TextureBuffer};
+use crate::core::shader::cache::{self, ProgramKind, SharedProgram};
+use crate::core::shader::data::ObjectData;
+use crate::core::math::mvp;
+use crate::core::project::Draw;
+use crate::core::math::transform::Transform;
+use nalgebra_glm as glm;
+
+const VERTEX_SHADER_SOURCE: &str = r#"
+    #version 330
+    layout (location = 0) in vec2 position;
+    layout (location = 1) in vec4 color;
+    layout (location = 2) in vec4 rect;
+    layout (location = 3) in vec4 texcoord_1;
+    layout (location = 4) in vec4 texcoord_2;
+    layout (location = 5) in float mode;
+    layout (location = 6) in mat4 model;
+
+    uniform mat4 projection;
+    uniform mat4 view;
+
+    out vec4 oColor;
+    out vec2 oPosition;
+    out vec2 oTexCoord;
+    flat out float oMode;
+
+    void main() {
+        vec2 scale_position = position * rect.zw;
+        vec2 offset_position = scale_position + rect.xy;
+        gl_Position = projection * view * model * vec4(offset_position, 0.0, 1.0);
+        oColor = color;
+        oPosition = position;
+        oMode = mode;
+
+        // only textures get corrected, text always uses the whole image
+        float tex_correction = mode > 1.5 && mode < 2.5 ? 0.000001 : 0.0;
+
+        if(gl_VertexID == 0) {
+            oTexCoord = vec2(texcoord_2.x - tex_correction, texcoord_2.y - tex_correction);
+        } else if(gl_VertexID == 1) {
+            oTexCoord = vec2(texcoord_1.z + tex_correction, texcoord_1.w - tex_correction);
+        } else if(gl_VertexID == 2) {
+            oTexCoord = vec2(texcoord_1.x + tex_correction, texcoord_1.y + tex_correction);
+        } else {
+            oTexCoord = vec2(texcoord_2.z - tex_correction, texcoord_2.w + tex_correction);
+        }
+    }
+"#;
+
+const FRAGMENT_SHADER_SOURCE: &str = r#"
+    #version 330
+
+    uniform sampler2D sampler;
+
+    in vec4 oColor;
+    in vec2 oPosition;
+    in vec2 oTexCoord;
+    flat in float oMode;
+
+    out vec4 color;
+
+    void main() {
+        if(oMode < 0.5) {
+            // rect
+            color = oColor;
+        } else if(oMode < 1.5) {
+            // circle
+            float dist = distance(oPosition, vec2(0.5));
+            float delta = fwidth(dist);
+            float alpha = smoothstep(0.49-delta, 0.49, dist);
+            color = mix(oColor, vec4(0.0), alpha);
+        } else if(oMode < 2.5) {
+            // texture
+            vec4 t = texture(sampler, oTexCoord);
+            t.a = t.a * oColor.a;
+            color = t;
+        } else {
+            // text
+            vec4 t = texture(sampler, oTexCoord);
+            color = vec4(oColor.rgb, t.a * oColor.a);
+        }
+    }
+"#;
+
+/*
+what a batched sprite is drawn as, textures and text share
+their texture buffer with the batch, so it stays alive until flush
+*/
+#[derive(Clone)]
+pub enum BatchKind {
+    Rect,
+    Circle,
+    Texture(Rc<TextureBuffer>),
+    Text(Rc<TextureBuffer>),
+}
+
+impl BatchKind {
+    // the mode the fragment shader switches on
+    fn mode(&self) -> f32 {
+        match self {
+            BatchKind::Rect => 0.0,
+            BatchKind::Circle => 1.0,
+            BatchKind::Texture(_) => 2.0,
+            BatchKind::Text(_) => 3.0,
+        }
+    }
+
+    // the texture that has to be bound
+    fn texture(&self) -> Option<Rc<TextureBuffer>> {
+        match self {
+            BatchKind::Texture(texture) | BatchKind::Text(texture) => Some(texture.clone()),
+            _ => None,
+        }
+    }
+}
+
+// the batch draws plain rects, so the style of a rect would get lost
+pub(crate) fn check(kind: &BatchKind, object_data: &ObjectData) -> Result<(), String> {
+    if let BatchKind::Rect = kind {
+        if object_data.styled() {
+            return Err("A rect with a radius, border, gradient or shadow cannot be drawn by a SpriteBatch.".to_string());
+        }
+    }
+    Ok(())
+}
+
+// color, offset + scale, 8 texcoords, mode and the model matrix
+const INSTANCE_SIZE: usize = 4 + 4 + 8 + 1 + 16;
+const STRIDE: usize = INSTANCE_SIZE * std::mem::size_of::<f32>();
+
+type Instance = [f32; INSTANCE_SIZE];
+
+/*
+how the sprites inside a layer are sorted
+Texture groups them by texture for the fewest draw calls,
+Order draws them by their order and Y draws sprites with a
+lower bottom edge first, like in top down games
+*/
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum LayerSort {
+    #[default]
+    Texture,
+    Order,
+    Y,
+}
+
+/**
+where a sprite is drawn, lower layers are drawn first.
+the order sorts sprites inside a layer that uses LayerSort::Order
+and breaks ties between sprites at the same height with LayerSort::Y
+```rust
+rect.submit(&mut batch, 2).unwrap();
+player.submit(&mut batch, DrawOrder::new(2).order(1.0)).unwrap();
+```
+*/
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct DrawOrder {
+    pub layer: i32,
+    pub order: f32,
+}
+
+impl DrawOrder {
+    pub fn new(layer: i32) -> Self {
+        Self {layer, order: 0.0}
+    }
+
+    // set the order inside the layer
+    pub fn order(mut self, order: f32) -> Self {
+        self.order = order;
+        self
+    }
+}
+
+impl From<i32> for DrawOrder {
+    fn from(layer: i32) -> Self {
+        DrawOrder::new(layer)
+    }
+}
+
+// a submitted sprite with everything needed to sort it
+struct Sprite {
+    layer: i32,
+    order: f32,
+    y: f32, // the bottom edge on the screen
+    texture: GLuint, // the id of the texture buffer, 0 for none
+    _texture_buffer: Option<Rc<TextureBuffer>>, // the buffer needs to stay alive until flush
+    instance: Instance,
+}
+
+impl Sprite {
+    fn new(kind: BatchKind, object_data: &ObjectData, transform: &Transform, draw_order: DrawOrder) -> Self {
+        let (offset_x, offset_y) = object_data.offset;
+        let (width, height) = object_data.dim;
+        let model = transform.matrix() * object_data.instance_matrix();
+
+        // the lowest corner, instances can be rotated or flipped
+        let bottom = [(0.0, 0.0), (width, 0.0), (0.0, height), (width, height)].iter()
+            .map(|(x, y)| (model * glm::vec4(offset_x + x, offset_y + y, 0.0, 1.0)).y)
+            .fold(f32::MIN, f32::max);
+
+        Sprite {
+            layer: draw_order.layer,
+            order: draw_order.order,
+            y: bottom,
+            texture: kind.texture().map_or(0, |texture| texture.id),
+            _texture_buffer: kind.texture(),
+            instance: instance(&kind, object_data, transform),
+        }
+    }
+}
+
+// a range of sorted sprites that is drawn with a single call
+#[derive(PartialEq, Debug)]
+struct Group {
+    texture: GLuint,
+    start: usize,
+    count: usize,
+}
+
+// create the per instance data of a sprite
+fn instance(kind: &BatchKind, object_data: &ObjectData, transform: &Transform) -> Instance {
+    let color = object_data.color;
+    let (offset_x, offset_y) = object_data.offset;
+    let (width, height) = object_data.dim;
+    // text objects always draw the whole image
+    let texcoord = match kind {
+        BatchKind::Text(_) => ObjectData::default().texcoord,
+        _ => object_data.texcoord,
+    };
+
+    let mut instance = [0.0; INSTANCE_SIZE];
+    instance[..4].copy_from_slice(&[color.r, color.g, color.b, object_data.opacity]);
+    instance[4..8].copy_from_slice(&[offset_x, offset_y, width, height]);
+    instance[8..16].copy_from_slice(&texcoord);
+    instance[16] = kind.mode();
+    // the instance transform is part of the model matrix
+    instance[17..].copy_from_slice((transform.matrix() * object_data.instance_matrix()).as_slice());
+    instance
+}
+
+// sort the sprites by layer and then by the sort of their layer,
+// the sort is stable so sprites keep their submit order otherwise
+fn sort(sprites: &mut [Sprite], layers: &HashMap<i32, LayerSort>) {
+    sprites.sort_by(|a, b| {
+        a.layer.cmp(&b.layer).then_with(|| {
+            match layers.get(&a.layer).copied().unwrap_or_default() {
+                LayerSort::Texture => a.texture.cmp(&b.texture),
+                LayerSort::Order => a.order.total_cmp(&b.order),
+                LayerSort::Y => a.y.total_cmp(&b.y).then(a.order.total_cmp(&b.order)),
+            }
+        })
+    });
+}
+
+// split sorted sprites into ranges that share a texture
+fn groups(sprites: &[Sprite]) -> Vec<Group> {
+    let mut groups: Vec<Group> = vec![];
+    for (i, sprite) in sprites.iter().enumerate() {
+        match groups.last_mut() {
+            Some(group) if group.texture == sprite.texture => group.count += 1,
+            _ => groups.push(Group {texture: sprite.texture, start: i, count: 1}),
+        }
+    }
+    groups
+}
+
+/**
+collects rects, circles, textures and text during update
+and draws them with as few draw calls as possible
+
+sprites are sorted by layer first, lower layers are drawn first.
+by default sprites inside a layer are grouped by texture, so sprites
+with different textures on the same layer can change their order.
+use set_layer_sort when the order inside a layer matters.
+the z value of a transform is not used for sorting
+
+```rust
+let mut batch = SpriteBatch::new().unwrap();
+
+// in update
+batch.set_layer_sort(1, LayerSort::Y);
+rect.submit(&mut batch, 0).unwrap();
+player.submit(&mut batch, 1).unwrap();
+batch.flush(&draw, &camera).unwrap();
+```
+*/
+pub struct SpriteBatch {
+    program: Rc<SharedProgram>,
+    vertex_array: VertexArray,
+    _model_buffer: Buffer, // the buffer needs to stay alive
+    instance_buffer: Buffer, // the buffer needs to stay alive
+    sprites: Vec<Sprite>,
+    layers: HashMap<i32, LayerSort>,
+    draw_calls: usize,
+}
+
+impl SpriteBatch {
+    // create the batch program and buffers
+    pub fn new() -> Result<Self, String> {
+        let model_data: [f32; 4*2] = [
+            1.0,  0.0,      // top right 0
+            0.0,  0.0,      // top left 1
+            0.0,  1.0,      // bottom left 2
+            1.0,  1.0,      // bottom right 3
+        ];
+
+        let program = cache::get(ProgramKind::Batch, VERTEX_SHADER_SOURCE, FRAGMENT_SHADER_SOURCE)?;
+
+        let vertex_array = VertexArray::new();
+        vertex_array.bind();
+
+        let model_buffer = Buffer::new(gl::ARRAY_BUFFER, gl::STATIC_DRAW);
+        model_buffer.set_data(&model_data.to_vec());
+
+        unsafe {
+            gl::VertexAttribPointer(0, 2, gl::FLOAT, gl::FALSE, 8, std::ptr::null()); // position
+            gl::EnableVertexAttribArray(0);
+        }
+
+        // the instance attributes are pointed to the buffer on every draw call
+        let instance_buffer = Buffer::new(gl::ARRAY_BUFFER, gl::STREAM_DRAW);
+        unsafe {
+            for location in 1..10 {
+                gl::VertexAttribDivisor(location, 1);
+                gl::EnableVertexAttribArray(location);
+            }
+        }
+
+        Ok(Self {
+            program,
+            vertex_array,
+            _model_buffer: model_buffer,
+            instance_buffer,
+            sprites: vec![],
+            layers: HashMap::new(),
+            draw_calls: 0,
+        })
+    }
+
+    // submit a single sprite
+    pub fn push(&mut self, kind: BatchKind, object_data: &ObjectData, transform: &Transform, draw_order: impl Into<DrawOrder>) {
+        self.sprites.push(Sprite::new(kind, object_data, transform, draw_order.into()));
+    }
+
+    // submit a plain rect, the radius, border, gradient and shadow are not drawn
+    pub fn rect(&mut self, object_data: &ObjectData, transform: &Transform, draw_order: impl Into<DrawOrder>) {
+        self.push(BatchKind::Rect, object_data, transform, draw_order);
+    }
+
+    // submit a circle
+    pub fn circle(&mut self, object_data: &ObjectData, transform: &Transform, draw_order: impl Into<DrawOrder>) {
+        self.push(BatchKind::Circle, object_data, transform, draw_order);
+    }
+
+    // set how the sprites of a layer are sorted
+    pub fn set_layer_sort(&mut self, layer: i32, layer_sort: LayerSort) {
+        self.layers.insert(layer, layer_sort);
+    }
+
+    // get how the sprites of a layer are sorted
+    pub fn layer_sort(&self, layer: i32) -> LayerSort {
+        self.layers.get(&layer).copied().unwrap_or_default()
+    }
+
+    // the number of sprites waiting for flush
+    pub fn len(&self) -> usize {
+        self.sprites.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sprites.is_empty()
+    }
+
+    // the number of draw calls of the last flush
+    pub fn draw_calls(&self) -> usize {
+        self.draw_calls
+    }
+
+    // throw away all submitted sprites without drawing them
+    pub fn clear(&mut self) {
+        self.sprites.clear();
+    }
+
+    /**
+    draw all submitted sprites to the screen and empty the batch
+    every group of sprites sharing a texture is one instanced draw call
+    */
+    pub fn flush(&mut self, draw: &Draw, camera: &Transform) -> Result<(), String> {
+        self.draw_calls = 0;
+        if self.sprites.is_empty() {
+            return Ok(());
+        }
+
+        sort(&mut self.sprites, &self.layers);
+        let groups = groups(&self.sprites);
+        let instance_data: Vec<f32> = self.sprites.iter().flat_map(|sprite| sprite.instance).collect();
+
+        // create the view projection matrixes, the model is part of every instance
+        let projection = mvp::ortho(&draw.window);
+        let view = camera.matrix();
+
+        unsafe {
+            self.program.bind();
+            self.vertex_array.bind();
+            self.program.set_matrices(&projection, &view, &mvp::identity());
+            self.instance_buffer.set_data(&instance_data);
+
+            for group in groups.iter() {
+                // point the attributes to the first instance of the group
+                let start = group.start * STRIDE;
+                let float = std::mem::size_of::<f32>();
+                gl::VertexAttribPointer(1, 4, gl::FLOAT, gl::FALSE, STRIDE as i32, start as *const _); // color
+                gl::VertexAttribPointer(2, 4, gl::FLOAT, gl::FALSE, STRIDE as i32, (start + 4 * float) as *const _); // offset + scale
+                gl::VertexAttribPointer(3, 4, gl::FLOAT, gl::FALSE, STRIDE as i32, (start + 8 * float) as *const _); // texcoord_1
+                gl::VertexAttribPointer(4, 4, gl::FLOAT, gl::FALSE, STRIDE as i32, (start + 12 * float) as *const _); // texcoord_2
+                gl::VertexAttribPointer(5, 1, gl::FLOAT, gl::FALSE, STRIDE as i32, (start + 16 * float) as *const _); // mode
+                for column in 0..4 {
+                    let offset = start + (17 + column * 4) * float;
+                    gl::VertexAttribPointer(6 + column as GLuint, 4, gl::FLOAT, gl::FALSE, STRIDE as i32, offset as *const _); // model
+                }
+
+                gl::BindTexture(gl::TEXTURE_2D, group.texture);
+                gl::DrawArraysInstanced(gl::TRIANGLE_FAN, 0, 4, group.count as i32);
+                self.draw_calls += 1;
+            }
+        }
+
+        self.sprites.clear();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::color::Color;
+    use crate::core::shader::data::{Gradient, Shadow};
+
+    // a texture buffer with a made up id, it is never dropped
+    // because there is no opengl context to delete it in tests
+    fn buffer(id: GLuint) -> Rc<TextureBuffer> {
+        let texture = Rc::new(TextureBuffer {id});
+        std::mem::forget(texture.clone());
+        texture
+    }
+
+    fn sprite(kind: BatchKind, layer: i32) -> Sprite {
+        Sprite::new(kind, &ObjectData::default(), &Transform::default(), layer.into())
+    }
+
+    #[test]
+    fn test_instance() {
+        let object_data = ObjectData {
+            offset: (1.0, 2.0),
+            dim: (3.0, 4.0),
+            opacity: 0.5,
+            texcoord: [0.5; 8],
+            ..ObjectData::default()
+        };
+
+        let mut transform = Transform::default();
+        transform.set(10.0, 20.0, 0.0);
+
+        let texture = instance(&BatchKind::Texture(buffer(7)), &object_data, &transform);
+        assert_eq!(0.5, texture[3]);
+        assert_eq!([1.0, 2.0, 3.0, 4.0], texture[4..8]);
+        assert_eq!([0.5; 8], texture[8..16]);
+        assert_eq!(2.0, texture[16]);
+        // the translation is in the last column
+        assert_eq!([10.0, 20.0, 0.0, 1.0], texture[29..33]);
+
+        // text ignores the texture coordinates
+        let text = instance(&BatchKind::Text(buffer(7)), &object_data, &transform);
+        assert_eq!(ObjectData::default().texcoord, text[8..16]);
+        assert_eq!(3.0, text[16]);
+    }
+
+    #[test]
+    fn test_check() {
+        let styled = ObjectData {radius: 4.0, ..ObjectData::default()};
+        assert!(check(&BatchKind::Rect, &ObjectData::default()).is_ok());
+        assert!(check(&BatchKind::Rect, &styled).is_err());
+        assert!(check(&BatchKind::Rect, &ObjectData {border: 1.0, ..ObjectData::default()}).is_err());
+        assert!(check(&BatchKind::Rect, &ObjectData {shadow: Some(Shadow::new(1.0, 1.0, 2.0, &Color::grey(0))), ..ObjectData::default()}).is_err());
+        assert!(check(&BatchKind::Rect, &ObjectData {gradient: Gradient::Radial {color: Color::grey(255)}, ..ObjectData::default()}).is_err());
+        // only rects have a style
+        assert!(check(&BatchKind::Circle, &styled).is_ok());
+    }
+
+    #[test]
+    fn test_keep_texture() {
+        // a sprite holds its texture until the batch is cleared
+        let texture = buffer(5);
+        let count = Rc::strong_count(&texture);
+        let sprite = sprite(BatchKind::Texture(texture.clone()), 0);
+        assert_eq!(count + 1, Rc::strong_count(&texture));
+        assert_eq!(5, sprite.texture);
+
+        drop(sprite);
+        assert_eq!(count, Rc::strong_count(&texture));
+    }
+
+    #[test]
+    fn test_sort() {
+        let mut sprites = vec![
+            sprite(BatchKind::Texture(buffer(2)), 1),
+            sprite(BatchKind::Rect, 1),
+            sprite(BatchKind::Texture(buffer(1)), 0),
+            sprite(BatchKind::Circle, 1),
+        ];
+        sort(&mut sprites, &HashMap::new());
+
+        let keys: Vec<(i32, GLuint, f32)> = sprites.iter().map(|s| (s.layer, s.texture, s.instance[16])).collect();
+        // the rect is submitted before the circle and stays in front of it
+        assert_eq!(vec![(0, 1, 2.0), (1, 0, 0.0), (1, 0, 1.0), (1, 2, 2.0)], keys);
+    }
+
+    #[test]
+    fn test_groups() {
+        let mut sprites = vec![
+            sprite(BatchKind::Rect, 0),
+            sprite(BatchKind::Texture(buffer(3)), 0),
+            sprite(BatchKind::Circle, 0),
+            sprite(BatchKind::Text(buffer(3)), 0),
+            sprite(BatchKind::Rect, 2),
+        ];
+        sort(&mut sprites, &HashMap::new());
+
+        // rects and circles share the program and need no texture
+        assert_eq!(vec![
+            Group {texture: 0, start: 0, count: 2},
+            Group {texture: 3, start: 2, count: 2},
+            Group {texture: 0, start: 4, count: 1},
+        ], groups(&sprites));
+        assert!(groups(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_sort_order() {
+        let mut sprites: Vec<Sprite> = [3.0, -1.0, 2.0, -1.0].iter().enumerate().map(|(i, order)| {
+            let texture = BatchKind::Texture(buffer(4 - i as GLuint));
+            Sprite::new(texture, &ObjectData::default(), &Transform::default(), DrawOrder::new(0).order(*order))
+        }).collect();
+
+        let layers = HashMap::from([(0, LayerSort::Order)]);
+        sort(&mut sprites, &layers);
+
+        // equal orders keep their submit order
+        let textures: Vec<GLuint> = sprites.iter().map(|s| s.texture).collect();
+        assert_eq!(vec![3, 1, 2, 4], textures);
+    }
+
+    #[test]
+    fn test_sort_y() {
+        let object_data = ObjectData {dim: (16.0, 32.0), ..ObjectData::default()};
+        let mut sprites: Vec<Sprite> = [(0, 50.0, 0.0), (1, 10.0, 0.0), (2, 10.0, -1.0), (3, 0.0, 0.0)].iter().map(|(id, y, order)| {
+            let mut transform = Transform::default();
+            transform.set(0.0, *y, 0.0);
+            Sprite::new(BatchKind::Texture(buffer(*id)), &object_data, &transform, DrawOrder::new(1).order(*order))
+        }).collect();
+        sprites.push(sprite(BatchKind::Rect, 0));
+
+        let layers = HashMap::from([(1, LayerSort::Y)]);
+        sort(&mut sprites, &layers);
+
+        // the bottom edge is used, the order breaks ties
+        assert_eq!(42.0, sprites[2].y);
+        let textures: Vec<GLuint> = sprites.iter().map(|s| s.texture).collect();
+        assert_eq!(vec![0, 3, 2, 1, 0], textures);
+    }
+}
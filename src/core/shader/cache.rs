@@ -0,0 +1,116 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::{Rc, Weak};
+use gl::types::*;
+use nalgebra_glm::Mat4;
+use crate::core::shader::Shader;
+use crate::core::shader::program::Program;
+
+// the shader programs of the objects
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ProgramKind {
+    Rect,
+    Circle,
+    Texture,
+    Text,
+    Mesh,
+    Batch,
+    Material,
+}
+
+// the locations of the matrices every object shader uses
+#[derive(Clone, Copy, Debug)]
+pub struct Matrices {
+    pub projection: GLint,
+    pub view: GLint,
+    pub model: GLint,
+}
+
+/*
+a compiled program that is shared by all objects of a kind
+with its uniform locations, they are looked up once after linking
+*/
+pub struct SharedProgram {
+    pub program: Program,
+    pub matrices: Matrices,
+}
+
+impl SharedProgram {
+    // compile and link the sources and look up the uniform locations
+    pub fn new(vertex_source: &str, fragment_source: &str) -> Result<SharedProgram, String> {
+        let vertex_shader = Shader::new(vertex_source, gl::VERTEX_SHADER)?;
+        let fragment_shader = Shader::new(fragment_source, gl::FRAGMENT_SHADER)?;
+        let program = Program::new(&vertex_shader, &fragment_shader)?;
+
+        let matrices = Matrices {
+            projection: program.get_uniform_location("projection")?,
+            view: program.get_uniform_location("view")?,
+            model: program.get_uniform_location("model")?,
+        };
+
+        Ok(SharedProgram {program, matrices})
+    }
+
+    pub fn bind(&self) {
+        self.program.bind();
+    }
+
+    // send the model view projection matrices, the program has to be bound
+    pub fn set_matrices(&self, projection: &Mat4, view: &Mat4, model: &Mat4) {
+        unsafe {
+            gl::UniformMatrix4fv(self.matrices.projection, 1, gl::FALSE, projection.as_ptr());
+            gl::UniformMatrix4fv(self.matrices.view, 1, gl::FALSE, view.as_ptr());
+            gl::UniformMatrix4fv(self.matrices.model, 1, gl::FALSE, model.as_ptr());
+        }
+    }
+}
+
+impl Default for SharedProgram {
+    // an empty program, -1 locations are ignored by opengl
+    fn default() -> Self {
+        SharedProgram {
+            program: Program::default(),
+            matrices: Matrices {projection: -1, view: -1, model: -1},
+        }
+    }
+}
+
+/*
+the cache only holds weak references, so a program is
+deleted on the graphics card when the last object using it
+is dropped. opengl contexts are bound to a thread,
+so every thread has its own cache
+*/
+thread_local! {
+    static PROGRAMS: RefCell<HashMap<ProgramKind, Weak<SharedProgram>>> = RefCell::new(HashMap::new());
+}
+
+/*
+returns the program of a kind, it is compiled
+the first time it is needed or when all objects
+that used it were dropped
+*/
+pub fn get(kind: ProgramKind, vertex_source: &str, fragment_source: &str) -> Result<Rc<SharedProgram>, String> {
+    if let Some(program) = PROGRAMS.with(|programs| programs.borrow().get(&kind).and_then(|weak| weak.upgrade())) {
+        return Ok(program);
+    }
+
+    let program = Rc::new(SharedProgram::new(vertex_source, fragment_source)?);
+    PROGRAMS.with(|programs| programs.borrow_mut().insert(kind, Rc::downgrade(&program)));
+    Ok(program)
+}
+
+// the number of programs that are alive
+pub fn count() -> usize {
+    PROGRAMS.with(|programs| {
+        programs.borrow().values().filter(|weak| weak.strong_count() > 0).count()
+    })
+}
+
+/*
+forget all programs, call this after a new opengl context was created
+so the next objects compile their programs for the new context
+*/
+pub fn clear() {
+    PROGRAMS.with(|programs| programs.borrow_mut().clear());
+}
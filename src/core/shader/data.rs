@@ -1,4 +1,6 @@
+use nalgebra_glm::Mat4;
 use crate::core::color::Color;
+use crate::core::math::mvp;
 use crate::core::shader::object::TextureCoordinate;
 
 /// add an Instance to a [InstancedShader](InstancedShader)
@@ -15,6 +17,49 @@ pub struct ObjectData {
     pub opacity: f32,
     pub offset: (f32, f32),
     pub texcoord: TextureCoordinate,
+    // the style of rects, a styled rect cannot be submitted to a SpriteBatch
+    pub radius: f32,
+    pub border: f32,
+    pub border_color: Color,
+    pub gradient: Gradient,
+    pub shadow: Option<Shadow>,
+    /*
+    the transform of a single instance on top of the transform of the shader,
+    the rotation is in radians and the pivot goes from 0, 0 top left
+    to 1, 1 bottom right of the instance. flipping mirrors the
+    instance at its pivot, so the default pivot flips it in place
+    */
+    pub rotation: f32,
+    pub pivot: (f32, f32),
+    pub scale: (f32, f32),
+    pub flip: (bool, bool),
+}
+
+/*
+fills a rect from its color to another color,
+the angle of a linear gradient is in radians, 0 goes from left to right
+a radial gradient goes from the center to the corners
+*/
+#[derive(Clone, Copy, Default)]
+pub enum Gradient {
+    #[default]
+    None,
+    Linear { color: Color, angle: f32 },
+    Radial { color: Color },
+}
+
+// a soft shadow behind a rect, blur is in pixels
+#[derive(Clone, Copy)]
+pub struct Shadow {
+    pub offset: (f32, f32),
+    pub blur: f32,
+    pub color: Color,
+}
+
+impl Shadow {
+    pub fn new(x_offset: f32, y_offset: f32, blur: f32, color: &Color) -> Self {
+        Self {offset: (x_offset, y_offset), blur, color: *color}
+    }
 }
 
 impl Default for ObjectData {
@@ -31,6 +76,91 @@ impl Default for ObjectData {
                 1.0, 1.0,            
                 1.0, 0.0,
             ],
+            radius: 0.0,
+            border: 0.0,
+            border_color: Color::default(),
+            gradient: Gradient::None,
+            shadow: None,
+            rotation: 0.0,
+            pivot: (0.5, 0.5),
+            scale: (1.0, 1.0),
+            flip: (false, false),
         }
     }
+}
+
+impl ObjectData {
+    // the per instance rotation, pivot and scale for the vertex shaders, flipping negates the scale
+    pub(crate) fn instance_data(&self) -> [f32; 5] {
+        let (x_scale, y_scale) = self.flip_scale();
+        [self.rotation, self.pivot.0, self.pivot.1, x_scale, y_scale]
+    }
+
+    // true if a rect has any style that a SpriteBatch cannot draw
+    pub fn styled(&self) -> bool {
+        self.radius != 0.0
+            || self.border != 0.0
+            || !matches!(self.gradient, Gradient::None)
+            || self.shadow.is_some()
+    }
+
+    // the matrix the vertex shaders apply to the instance, around its pivot in pixels
+    pub fn instance_matrix(&self) -> Mat4 {
+        let (x_scale, y_scale) = self.flip_scale();
+        let pivot_x = self.offset.0 + self.pivot.0 * self.dim.0;
+        let pivot_y = self.offset.1 + self.pivot.1 * self.dim.1;
+
+        let matrix = mvp::translate(&mvp::identity(), pivot_x, pivot_y, 0.0);
+        let matrix = mvp::rotate(&matrix, self.rotation, 0.0, 0.0, 1.0);
+        let matrix = mvp::scale(&matrix, x_scale, y_scale, 1.0);
+        mvp::translate(&matrix, -pivot_x, -pivot_y, 0.0)
+    }
+
+    fn flip_scale(&self) -> (f32, f32) {
+        let (x_flip, y_flip) = self.flip;
+        (
+            if x_flip { -self.scale.0 } else { self.scale.0 },
+            if y_flip { -self.scale.1 } else { self.scale.1 },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(matrix: &Mat4, x: f32, y: f32) -> (f32, f32) {
+        let point = matrix * nalgebra_glm::vec4(x, y, 0.0, 1.0);
+        (point.x, point.y)
+    }
+
+    fn assert_near(expected: (f32, f32), actual: (f32, f32)) {
+        assert!((expected.0 - actual.0).abs() < 0.001 && (expected.1 - actual.1).abs() < 0.001, "{:?} != {:?}", expected, actual);
+    }
+
+    #[test]
+    fn test_instance_matrix() {
+        let mut object_data = ObjectData {
+            dim: (20.0, 10.0),
+            offset: (100.0, 50.0),
+            ..ObjectData::default()
+        };
+        assert_near((100.0, 50.0), apply(&object_data.instance_matrix(), 100.0, 50.0));
+
+        // a quarter turn around the center
+        object_data.rotation = std::f32::consts::FRAC_PI_2;
+        assert_near((115.0, 45.0), apply(&object_data.instance_matrix(), 100.0, 50.0));
+
+        // flipping mirrors in place
+        object_data.rotation = 0.0;
+        object_data.flip = (true, false);
+        assert_near((120.0, 50.0), apply(&object_data.instance_matrix(), 100.0, 50.0));
+        assert_eq!([0.0, 0.5, 0.5, -1.0, 1.0], object_data.instance_data());
+
+        // scale around the top left
+        object_data.flip = (false, false);
+        object_data.pivot = (0.0, 0.0);
+        object_data.scale = (2.0, 3.0);
+        assert_near((140.0, 80.0), apply(&object_data.instance_matrix(), 120.0, 60.0));
+    }
 }
\ No newline at end of file
@@ -1,10 +1,14 @@
 use crate::core::shader::object::{Object, ObjectState};
 use crate::core::*;
-use crate::core::shader::object::{circle::Circle, rect::Rect, text::Text, texture::Texture};
+use crate::core::shader::object::{circle::Circle, rect::Rect, text::Text, texture::Texture, mesh::Mesh};
 use crate::core::color::Color;
 use crate::core::resource::font::Font;
 use crate::core::math::collision;
 use crate::core::shader::object::TextureCoordinate;
+use crate::core::shader::data::{Gradient, Shadow};
+use crate::core::shader::batch::{self, SpriteBatch, DrawOrder};
+use crate::core::shader::target::RenderTarget;
+use crate::core::shader::material::Material;
 
 // this enum will 
 // help in not rendering something because you forgot the
@@ -71,6 +75,39 @@ impl InstancedShader {
         Ok(component)
     }
 
+    /*
+    create a new InstancedShader out of triangles in the range 0 to 1,
+    the dim of an instance scales them like a rect
+    */
+    pub fn mesh(triangles: &[(f32, f32)]) -> Result<Self, String> {
+        if triangles.is_empty() || !triangles.len().is_multiple_of(3) {
+            return Err(format!("Cannot create a mesh out of {} points.", triangles.len()));
+        }
+
+        let component = Self {
+            object: Box::new(Mesh::new(triangles)),
+            object_data: vec![], 
+            transform: Transform::default(),
+            state: InstancedComponentState::NotLoaded,
+        };
+
+        Ok(component)
+    }
+
+    // create a new InstancedShader that draws the texture of a render target
+    pub fn target(target: &RenderTarget) -> Result<Self, String> {
+        let texture = Texture::from_buffer(target.texture());
+
+        let component = Self {
+            object: Box::new(texture),
+            object_data: vec![], 
+            transform: Transform::default(),
+            state: InstancedComponentState::NotLoaded,
+        };
+
+        Ok(component)
+    }
+
     // create a new text InstancedShader
     pub fn text(text: &str, font: &Font, font_size: i32) -> Result<Self, String> {
         // create the text as rgba image
@@ -137,6 +174,39 @@ impl InstancedShader {
         Ok(())
     } 
 
+    // draw the InstancedShader with a material instead of its own program,
+    // a material is not used when the InstancedShader is submitted to a SpriteBatch
+    pub fn set_material(&mut self, material: &Material) -> Result<(), String> {
+        self.object.set_material(Some(material.clone()))
+    }
+
+    // draw the InstancedShader with its own program again
+    pub fn remove_material(&mut self) -> Result<(), String> {
+        self.object.set_material(None)
+    }
+
+    // submit all instances to a SpriteBatch instead of drawing them
+    pub fn submit(&self, batch: &mut SpriteBatch, draw_order: impl Into<DrawOrder>) -> Result<(), String> {
+        if let InstancedComponentState::NotLoaded = self.state {
+            return Err("Cannot submit without creating the model data. Please call load on the InstancedComponent.".to_string());
+        }
+
+        let kind = match self.object.batch_kind() {
+            Some(kind) => kind,
+            None => return Err("This InstancedShader cannot be drawn by a SpriteBatch.".to_string()),
+        };
+        // submit nothing if one instance cannot be batched
+        for object_data in self.object_data.iter() {
+            batch::check(&kind, object_data)?;
+        }
+
+        let draw_order = draw_order.into();
+        for object_data in self.object_data.iter() {
+            batch.push(kind.clone(), object_data, &self.transform, draw_order);
+        }
+        Ok(())
+    }
+
     // set the width and the height of a transform data i of the InstancedShader
     pub fn set_dim(&mut self, i: usize, width: f32, height: f32) -> Result<(), String> {
         self.index_oob(i)?;
@@ -246,12 +316,142 @@ impl InstancedShader {
         Ok(self.object_data[i].texcoord)
     }
 
+    // set the corner radius of transform data i of a rect InstancedShader
+    pub fn set_radius(&mut self, i: usize, radius: f32) -> Result<(), String> {
+        self.index_oob(i)?;
+        self.object_data[i].radius = radius;
+        self.object.set(i, &self.object_data[i]);
+        self.object.set_state(ObjectState::Reload);
+        Ok(())
+    }
+
+    // get the corner radius of transform data i of the InstancedShader
+    pub fn radius(&self, i: usize) -> Result<f32, String> {
+        self.index_oob(i)?;
+        Ok(self.object_data[i].radius)
+    }
+
+    // set the border width and color of transform data i of a rect InstancedShader
+    pub fn set_border(&mut self, i: usize, width: f32, color: &Color) -> Result<(), String> {
+        self.index_oob(i)?;
+        self.object_data[i].border = width;
+        self.object_data[i].border_color = *color;
+        self.object.set(i, &self.object_data[i]);
+        self.object.set_state(ObjectState::Reload);
+        Ok(())
+    }
+
+    // get the border width and color of transform data i of the InstancedShader
+    pub fn border(&self, i: usize) -> Result<(f32, Color), String> {
+        self.index_oob(i)?;
+        Ok((self.object_data[i].border, self.object_data[i].border_color))
+    }
+
+    // set the gradient of transform data i of a rect InstancedShader
+    pub fn set_gradient(&mut self, i: usize, gradient: Gradient) -> Result<(), String> {
+        self.index_oob(i)?;
+        self.object_data[i].gradient = gradient;
+        self.object.set(i, &self.object_data[i]);
+        self.object.set_state(ObjectState::Reload);
+        Ok(())
+    }
+
+    // get the gradient of transform data i of the InstancedShader
+    pub fn gradient(&self, i: usize) -> Result<Gradient, String> {
+        self.index_oob(i)?;
+        Ok(self.object_data[i].gradient)
+    }
+
+    // set the drop shadow of transform data i of a rect InstancedShader
+    pub fn set_shadow(&mut self, i: usize, shadow: Option<Shadow>) -> Result<(), String> {
+        self.index_oob(i)?;
+        self.object_data[i].shadow = shadow;
+        self.object.set(i, &self.object_data[i]);
+        self.object.set_state(ObjectState::Reload);
+        Ok(())
+    }
+
+    // get the drop shadow of transform data i of the InstancedShader
+    pub fn shadow(&self, i: usize) -> Result<Option<Shadow>, String> {
+        self.index_oob(i)?;
+        Ok(self.object_data[i].shadow)
+    }
+
+    // set the rotation of transform data i of the InstancedShader in radians around its pivot
+    pub fn set_rotation(&mut self, i: usize, angle: f32) -> Result<(), String> {
+        self.index_oob(i)?;
+        self.object_data[i].rotation = angle;
+        self.object.set(i, &self.object_data[i]);
+        self.object.set_state(ObjectState::Reload);
+        Ok(())
+    }
+
+    // get the rotation of transform data i of the InstancedShader
+    pub fn rotation(&self, i: usize) -> Result<f32, String> {
+        self.index_oob(i)?;
+        Ok(self.object_data[i].rotation)
+    }
+
+    // set the pivot of transform data i of the InstancedShader,
+    // from 0, 0 top left to 1, 1 bottom right of the instance
+    pub fn set_pivot(&mut self, i: usize, x: f32, y: f32) -> Result<(), String> {
+        self.index_oob(i)?;
+        self.object_data[i].pivot = (x, y);
+        self.object.set(i, &self.object_data[i]);
+        self.object.set_state(ObjectState::Reload);
+        Ok(())
+    }
+
+    // get the pivot of transform data i of the InstancedShader
+    pub fn pivot(&self, i: usize) -> Result<(f32, f32), String> {
+        self.index_oob(i)?;
+        Ok(self.object_data[i].pivot)
+    }
+
+    // set the scale of transform data i of the InstancedShader around its pivot
+    pub fn set_scale(&mut self, i: usize, x: f32, y: f32) -> Result<(), String> {
+        self.index_oob(i)?;
+        self.object_data[i].scale = (x, y);
+        self.object.set(i, &self.object_data[i]);
+        self.object.set_state(ObjectState::Reload);
+        Ok(())
+    }
+
+    // get the scale of transform data i of the InstancedShader
+    pub fn scale(&self, i: usize) -> Result<(f32, f32), String> {
+        self.index_oob(i)?;
+        Ok(self.object_data[i].scale)
+    }
+
+    // mirror transform data i of the InstancedShader horizontally and vertically at its pivot
+    pub fn set_flip(&mut self, i: usize, horizontal: bool, vertical: bool) -> Result<(), String> {
+        self.index_oob(i)?;
+        self.object_data[i].flip = (horizontal, vertical);
+        self.object.set(i, &self.object_data[i]);
+        self.object.set_state(ObjectState::Reload);
+        Ok(())
+    }
+
+    // get the horizontal and vertical flip of transform data i of the InstancedShader
+    pub fn flip(&self, i: usize) -> Result<(bool, bool), String> {
+        self.index_oob(i)?;
+        Ok(self.object_data[i].flip)
+    }
+
     // collision for an instance
     pub fn instance_collides(&self, i: usize, x: f32, y: f32) -> Result<bool, String> {
+        self.index_oob(i)?;
         let (tx, ty, _) = self.transform.pos();
         let (x_offset, y_offset) = self.offset(i)?;
         let (width, height) = self.dim(i)?;
-        let collides = collision::point_in_rect(x, y, tx + x_offset, ty + y_offset, width, height);
+
+        // undo the rotation, scale and flip of the instance
+        let point = match self.object_data[i].instance_matrix().try_inverse() {
+            Some(inverse) => inverse * nalgebra_glm::vec4(x - tx, y - ty, 0.0, 1.0),
+            None => return Ok(false),
+        };
+
+        let collides = collision::point_in_rect(point.x, point.y, x_offset, y_offset, width, height);
         Ok(collides)
     }
 
@@ -0,0 +1,334 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use nalgebra_glm::Mat4;
+use crate::core::shader::cache::{ProgramKind, SharedProgram};
+use crate::core::shader::object::TextureBuffer;
+use crate::core::shader::uniform::Uniform;
+use crate::core::shader::watch::SourceFile;
+use crate::core::resource::image::Image;
+
+struct MaterialData {
+    vertex_source: Option<String>,
+    fragment_source: String,
+    // the files the sources were loaded from, for hot reloading
+    vertex_file: Option<SourceFile>,
+    fragment_file: Option<SourceFile>,
+    // the programs with the vertex stage of the object they were compiled for
+    programs: HashMap<ProgramKind, (&'static str, Rc<SharedProgram>)>,
+    uniforms: HashMap<String, Uniform>,
+    textures: Vec<(String, Rc<TextureBuffer>)>,
+}
+
+impl MaterialData {
+    // the files of the material for error messages
+    fn paths(&self) -> String {
+        let paths: Vec<&str> = [&self.vertex_file, &self.fragment_file].iter()
+            .filter_map(|file| file.as_ref().map(|file| file.path()))
+            .collect();
+        format!("'{}'", paths.join("', '"))
+    }
+}
+
+/**
+a custom shader program for a Shader or InstancedShader
+
+a fragment material plugs into the vertex stage of the object
+it is attached to, which gives every object the same inputs
+```glsl
+#version 330
+in vec4 oColor; // color and opacity, white for textures
+in vec2 oTexCoord; // the texture coordinate
+in vec2 oPosition; // from 0, 0 top left to 1, 1 bottom right of the quad
+uniform sampler2D sampler; // the texture of textures and text
+out vec4 color;
+```
+a material with its own vertex stage has to use the attributes
+of the object and the projection, view and model uniforms
+
+a material is a handle, clones share the program, uniforms and textures
+```rust
+let material = Material::fragment(WAVE_SOURCE);
+// or load it from a file and poll it in update to reload it on changes
+let material = Material::fragment_file("shaders/wave.frag")?;
+material.set_float("time", 0.0);
+material.set_texture("noise", &noise)?;
+player.set_material(&material)?;
+```
+*/
+#[derive(Clone)]
+pub struct Material {
+    data: Rc<RefCell<MaterialData>>,
+}
+
+impl Material {
+    // create a material with its own vertex and fragment stage
+    pub fn new(vertex_source: &str, fragment_source: &str) -> Self {
+        Self::create(Some(vertex_source.to_string()), fragment_source)
+    }
+
+    // create a material that uses the vertex stage of the object
+    pub fn fragment(fragment_source: &str) -> Self {
+        Self::create(None, fragment_source)
+    }
+
+    // load a material with its own vertex and fragment stage from files
+    pub fn files(vertex_path: &str, fragment_path: &str) -> Result<Self, String> {
+        let vertex_file = SourceFile::new(vertex_path)?;
+        let fragment_file = SourceFile::new(fragment_path)?;
+
+        let material = Self::new(vertex_file.source(), fragment_file.source());
+        material.data.borrow_mut().vertex_file = Some(vertex_file);
+        material.data.borrow_mut().fragment_file = Some(fragment_file);
+        Ok(material)
+    }
+
+    // load a material that uses the vertex stage of the object from a file
+    pub fn fragment_file(fragment_path: &str) -> Result<Self, String> {
+        let fragment_file = SourceFile::new(fragment_path)?;
+
+        let material = Self::fragment(fragment_file.source());
+        material.data.borrow_mut().fragment_file = Some(fragment_file);
+        Ok(material)
+    }
+
+    fn create(vertex_source: Option<String>, fragment_source: &str) -> Self {
+        let data = MaterialData {
+            vertex_source,
+            fragment_source: fragment_source.to_string(),
+            vertex_file: None,
+            fragment_file: None,
+            programs: HashMap::new(),
+            uniforms: HashMap::new(),
+            textures: vec![],
+        };
+
+        Self {data: Rc::new(RefCell::new(data))}
+    }
+
+    // set any uniform, it is sent every time an object with the material is drawn
+    pub fn set_uniform(&self, name: &str, value: impl Into<Uniform>) {
+        self.data.borrow_mut().uniforms.insert(name.to_string(), value.into());
+    }
+
+    pub fn set_int(&self, name: &str, value: i32) {
+        self.set_uniform(name, Uniform::Int(value));
+    }
+
+    pub fn set_float(&self, name: &str, value: f32) {
+        self.set_uniform(name, Uniform::Float(value));
+    }
+
+    pub fn set_vec2(&self, name: &str, x: f32, y: f32) {
+        self.set_uniform(name, Uniform::Vec2(x, y));
+    }
+
+    pub fn set_vec4(&self, name: &str, x: f32, y: f32, z: f32, w: f32) {
+        self.set_uniform(name, Uniform::Vec4(x, y, z, w));
+    }
+
+    pub fn set_mat4(&self, name: &str, value: &Mat4) {
+        self.set_uniform(name, Uniform::Mat4(*value));
+    }
+
+    // get the value of a uniform
+    pub fn uniform(&self, name: &str) -> Option<Uniform> {
+        self.data.borrow().uniforms.get(name).copied()
+    }
+
+    // bind an image to a sampler uniform
+    pub fn set_texture(&self, name: &str, image: &Image) -> Result<(), String> {
+        let texture = TextureBuffer::new();
+        texture.set_data(&image.to_rgba_image());
+        self.set_texture_buffer(name, Rc::new(texture))
+    }
+
+    /*
+    bind a texture buffer to a sampler uniform, like the texture of a render target.
+    texture unit 0 belongs to the object, so there are 15 units left
+    */
+    pub fn set_texture_buffer(&self, name: &str, texture: Rc<TextureBuffer>) -> Result<(), String> {
+        let mut data = self.data.borrow_mut();
+
+        if let Some(i) = data.textures.iter().position(|(sampler, _)| sampler == name) {
+            data.textures[i].1 = texture;
+            return Ok(());
+        }
+
+        if data.textures.len() >= 15 {
+            return Err(format!("Cannot bind the texture '{}', a material can have 15 textures.", name));
+        }
+
+        data.textures.push((name.to_string(), texture));
+        Ok(())
+    }
+
+    /*
+    the program for an object kind, a fragment material is compiled
+    once for every kind with the vertex stage of the object
+    */
+    pub fn program(&self, kind: ProgramKind, vertex_source: &'static str) -> Result<Rc<SharedProgram>, String> {
+        let mut data = self.data.borrow_mut();
+        let key = if data.vertex_source.is_some() { ProgramKind::Material } else { kind };
+
+        if let Some((_, program)) = data.programs.get(&key) {
+            return Ok(program.clone());
+        }
+
+        let program = Rc::new(SharedProgram::new(data.vertex_source.as_deref().unwrap_or(vertex_source), &data.fragment_source)?);
+        data.programs.insert(key, (vertex_source, program.clone()));
+        Ok(program)
+    }
+
+    /**
+    check if the source files changed and recompile the programs,
+    call it in update while developing. returns true after a reload.
+    when a source does not compile the last good programs are kept
+    and the error contains the info log of the compiler
+    */
+    pub fn poll(&self) -> Result<bool, String> {
+        let mut data = self.data.borrow_mut();
+        let data = &mut *data;
+
+        // poll both files before failing, so no change is skipped
+        let vertex_source = poll_file(data.vertex_file.as_mut());
+        let fragment_source = poll_file(data.fragment_file.as_mut());
+
+        // when one file cannot be read the other one is read again next time
+        let (vertex_source, fragment_source) = match (vertex_source, fragment_source) {
+            (Ok(vertex_source), Ok(fragment_source)) => (vertex_source, fragment_source),
+            (vertex_source, fragment_source) => {
+                if let (Ok(Some(_)), Some(file)) = (&vertex_source, data.vertex_file.as_mut()) {
+                    file.forget();
+                }
+                if let (Ok(Some(_)), Some(file)) = (&fragment_source, data.fragment_file.as_mut()) {
+                    file.forget();
+                }
+                return Err(vertex_source.and(fragment_source).unwrap_err());
+            }
+        };
+
+        if vertex_source.is_none() && fragment_source.is_none() {
+            return Ok(false);
+        }
+
+        let vertex_source = vertex_source.or_else(|| data.vertex_source.clone());
+        let fragment_source = fragment_source.unwrap_or_else(|| data.fragment_source.clone());
+
+        // compile everything first, so nothing changes on an error
+        let mut programs = HashMap::new();
+        for (kind, (object_vertex_source, _)) in data.programs.iter() {
+            let vertex = vertex_source.as_deref().unwrap_or(object_vertex_source);
+            match SharedProgram::new(vertex, &fragment_source) {
+                Ok(program) => programs.insert(*kind, (*object_vertex_source, Rc::new(program))),
+                Err(log) => return Err(format!("Could not reload the material {}: {}", data.paths(), log)),
+            };
+        }
+
+        data.vertex_source = vertex_source;
+        data.fragment_source = fragment_source;
+        data.programs = programs;
+        Ok(true)
+    }
+
+    // send the uniforms and bind the textures, the program has to be bound
+    pub fn bind(&self, program: &SharedProgram) -> Result<(), String> {
+        let data = self.data.borrow();
+
+        for (name, uniform) in data.uniforms.iter() {
+            uniform.set(program.program.get_uniform_location(name)?);
+        }
+
+        for (i, (name, texture)) in data.textures.iter().enumerate() {
+            let unit = i as u32 + 1;
+            unsafe {
+                gl::ActiveTexture(gl::TEXTURE0 + unit);
+            }
+            texture.bind();
+            Uniform::Int(unit as i32).set(program.program.get_uniform_location(name)?);
+        }
+
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0);
+        }
+
+        Ok(())
+    }
+}
+
+// the new source of a file if it changed
+fn poll_file(file: Option<&mut SourceFile>) -> Result<Option<String>, String> {
+    match file {
+        Some(file) => Ok(file.poll()?.map(|source| source.to_string())),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uniforms() {
+        let material = Material::fragment("#version 330");
+        material.set_float("time", 1.5);
+        material.set_vec2("direction", 1.0, 0.0);
+        material.set_uniform("steps", 4);
+
+        // clones share the uniforms
+        let clone = material.clone();
+        clone.set_float("time", 2.0);
+
+        assert_eq!(Some(Uniform::Float(2.0)), material.uniform("time"));
+        assert_eq!(Some(Uniform::Vec2(1.0, 0.0)), material.uniform("direction"));
+        assert_eq!(Some(Uniform::Int(4)), material.uniform("steps"));
+        assert_eq!(None, material.uniform("missing"));
+    }
+
+    #[test]
+    fn test_poll() {
+        let path = std::env::temp_dir().join("d7engine_test_material.frag");
+        let path = path.to_str().unwrap();
+        crate::core::file::write(path, "#version 330").unwrap();
+
+        let material = Material::fragment_file(path).unwrap();
+        assert_eq!(Ok(false), material.poll());
+
+        crate::core::file::write(path, "#version 330 core").unwrap();
+        let later = std::time::SystemTime::now() + std::time::Duration::from_secs(10);
+        std::fs::File::options().write(true).open(path).unwrap().set_modified(later).unwrap();
+
+        // nothing is compiled yet, so only the source changes
+        assert_eq!(Ok(true), material.poll());
+        assert_eq!("#version 330 core", material.data.borrow().fragment_source);
+
+        std::fs::remove_file(path).unwrap();
+        assert!(Material::fragment_file(path).is_err());
+    }
+
+    #[test]
+    fn test_poll_missing_file() {
+        let vertex = std::env::temp_dir().join("d7engine_test_missing.vert");
+        let fragment = std::env::temp_dir().join("d7engine_test_missing.frag");
+        let (vertex, fragment) = (vertex.to_str().unwrap(), fragment.to_str().unwrap());
+        crate::core::file::write(vertex, "vertex").unwrap();
+        crate::core::file::write(fragment, "fragment").unwrap();
+
+        let material = Material::files(vertex, fragment).unwrap();
+
+        // change the vertex file while the fragment file is gone
+        crate::core::file::write(vertex, "vertex 2").unwrap();
+        let later = std::time::SystemTime::now() + std::time::Duration::from_secs(10);
+        std::fs::File::options().write(true).open(vertex).unwrap().set_modified(later).unwrap();
+        std::fs::remove_file(fragment).unwrap();
+        assert!(material.poll().is_err());
+
+        // the vertex change is still picked up once both files can be read
+        crate::core::file::write(fragment, "fragment").unwrap();
+        assert_eq!(Ok(true), material.poll());
+        assert_eq!(Some("vertex 2"), material.data.borrow().vertex_source.as_deref());
+
+        std::fs::remove_file(vertex).unwrap();
+        std::fs::remove_file(fragment).unwrap();
+    }
+}
@@ -1,10 +1,34 @@
 use gl::types::*;
 use crate::core::*;
+use crate::core::shader::batch::BatchKind;
+use crate::core::shader::material::Material;
 
 pub mod rect;
 pub mod texture;
 pub mod text;
 pub mod circle;
+pub mod mesh;
+
+/*
+the per instance rotation, pivot, scale and flip of every object,
+the vertex shaders have the rotation and instance_scale attributes
+and get this function in place of their INSTANCE_TRANSFORM line
+*/
+pub const INSTANCE_TRANSFORM: &str = r#"
+    // rotate, scale and flip a point of the instance around its pivot
+    vec2 instance(vec2 point, vec2 size) {
+        vec2 pivot = rotation.yz * size;
+        vec2 p = (point - pivot) * instance_scale;
+        float c = cos(rotation.x);
+        float s = sin(rotation.x);
+        return vec2(c * p.x - s * p.y, s * p.x + c * p.y) + pivot;
+    }
+"#;
+
+// put the instance transform into a vertex shader source
+pub fn splice_instance_transform(source: &str) -> String {
+    source.replacen("    // INSTANCE_TRANSFORM\n", INSTANCE_TRANSFORM, 1)
+}
 
 /*
 a buffer holds the information
@@ -185,6 +209,30 @@ impl TextureBuffer {
             gl::GenerateMipmap(gl::TEXTURE_2D);
         }
     }
+
+    // reserve an empty texture, used as the color attachment of a render target
+    pub fn allocate(&self, width: u32, height: u32) {
+        self.bind();
+
+        unsafe {
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                gl::RGBA as i32,
+                width as i32,
+                height as i32,
+                0,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                std::ptr::null(),
+            );
+        }
+    }
 }
 
 impl Default for TextureBuffer {
@@ -217,6 +265,8 @@ pub trait Object {
     fn remove_all(&mut self);
     fn draw(&mut self, draw: &Draw, camera: &Transform, model_transform: &Transform) -> Result<(), String>;
     fn set_state(&mut self, object_state: ObjectState);
+    fn batch_kind(&self) -> Option<BatchKind>;
+    fn set_material(&mut self, material: Option<Material>) -> Result<(), String>;
 }
 
 // describes the object state
@@ -0,0 +1,251 @@
+use std::rc::Rc;
+use std::sync::LazyLock;
+use crate::core::shader::object::{VertexArray, Buffer, ObjectState, Object, splice_instance_transform};
+use crate::core::shader::batch::BatchKind;
+use crate::core::shader::material::Material;
+use crate::core::shader::cache::{self, ProgramKind, SharedProgram};
+use crate::core::shader::data::ObjectData;
+use crate::core::math::mvp;
+use crate::core::project::Draw;
+use crate::core::math::transform::Transform;
+
+static VERTEX_SHADER_SOURCE: LazyLock<String> = LazyLock::new(|| splice_instance_transform(r#"
+    #version 330
+    layout (location = 0) in vec2 position;
+    layout (location = 1) in vec4 color;
+    layout (location = 2) in vec2 offset;
+    layout (location = 3) in vec2 scale;
+    layout (location = 4) in vec3 rotation; // angle, pivot x, pivot y
+    layout (location = 5) in vec2 instance_scale; // negative when flipped
+    
+    uniform mat4 projection;
+    uniform mat4 view;
+    uniform mat4 model;
+
+    out vec4 oColor;
+    out vec2 oTexCoord; // for fragment materials
+    out vec2 oPosition; // for fragment materials
+
+    // INSTANCE_TRANSFORM
+
+    void main() {
+        vec2 scale_position = position * scale;
+        vec2 offset_position = instance(scale_position, scale) + offset;
+        gl_Position = projection * view * model * vec4(offset_position, 0.0, 1.0);
+        oColor = color;
+        oTexCoord = vec2(position.x, 1.0 - position.y);
+        oPosition = position;
+    }
+"#));
+
+const FRAGMENT_SHADER_SOURCE: &str = r#"
+    #version 330
+    in vec4 oColor;
+
+    out vec4 color;
+
+    void main() {
+        color = vec4(oColor);
+    }
+"#;
+
+/*
+A mesh is an instanced 
+implementation of a the shaders above
+it holds a list of triangles that is used for drawing 
+lines and polygons to the screen
+
+the points of the triangles are in the range 0 to 1,
+so a mesh is placed and scaled like a rect
+*/
+type TransformData = [f32; 13];
+
+pub struct Mesh {
+    vertices: Vec<(f32, f32)>,
+    program: Rc<SharedProgram>,
+    vertex_array: VertexArray,
+    model_buffer: Buffer, // the buffer needs to stay alive
+    transform_buffer: Buffer, // the buffer needs to stay alive
+    transform_data: Vec<TransformData>,
+    state: ObjectState,
+    material: Option<Material>,
+}
+
+impl Mesh {
+    // creates an empty Mesh out of triangles
+    pub fn new(vertices: &[(f32, f32)]) -> Self {
+        Self {
+            vertices: vertices.to_vec(),
+            program: Rc::new(SharedProgram::default()),
+            vertex_array: VertexArray::default(),
+            model_buffer: Buffer::default(),
+            transform_buffer: Buffer::default(),
+            transform_data: vec![],
+            state: ObjectState::Ok,
+            material: None,
+        }
+    }
+
+    // the program of the material or the shared program of the object
+    fn program(&self) -> Result<Rc<SharedProgram>, String> {
+        match &self.material {
+            Some(material) => material.program(ProgramKind::Mesh, &VERTEX_SHADER_SOURCE),
+            None => cache::get(ProgramKind::Mesh, &VERTEX_SHADER_SOURCE, FRAGMENT_SHADER_SOURCE),
+        }
+    }
+}
+
+impl Object for Mesh {
+    // add an new Mesh to the transform data
+    fn add(&mut self, object_data: &ObjectData) {
+        let color = object_data.color;
+        let opacity = object_data.opacity;
+        let (offset_x, offset_y) = object_data.offset;
+        let (width, height) = object_data.dim;
+
+        let [rotation, pivot_x, pivot_y, x_scale, y_scale] = object_data.instance_data();
+
+        let transform_data: TransformData = [
+            color.r, color.g, color.b, opacity, offset_x, offset_y, width, height,
+            rotation, pivot_x, pivot_y, x_scale, y_scale,
+        ];
+
+        self.transform_data.push(transform_data);
+    }
+
+    /// set the component data
+    /// for a specific element 'i' of the transform data vector
+    fn set(&mut self, i: usize, object_data: &ObjectData) {
+        let color = object_data.color;
+        let opacity = object_data.opacity;
+        let (offset_x, offset_y) = object_data.offset;
+        let (width, height) = object_data.dim;
+
+        let [rotation, pivot_x, pivot_y, x_scale, y_scale] = object_data.instance_data();
+
+        let transform_data: TransformData = [
+            color.r, color.g, color.b, opacity, offset_x, offset_y, width, height,
+            rotation, pivot_x, pivot_y, x_scale, y_scale,
+        ];
+
+        self.transform_data[i] = transform_data;
+    }
+
+    // removes a mesh from 
+    // the transform data
+    fn remove(&mut self, i: usize) {
+        self.transform_data.remove(i);
+    }
+
+    // removes all meshes from 
+    // the transform data
+    fn remove_all(&mut self) {
+        self.transform_data = vec![];
+    }
+
+
+    // create shaders and buffers
+    fn load(&mut self) -> Result<(), String> {
+        let model_data: Vec<f32> = self.vertices.iter().flat_map(|(x, y)| [*x, *y]).collect();
+
+        let transform_data = self.transform_data.concat();
+
+        unsafe {
+            // get the program of the material or the shared shaderprogram
+            self.program = self.program()?;
+
+            // create a new buffer for our vertex array (model + transform data)
+            self.vertex_array = VertexArray::new();
+            self.vertex_array.bind(); 
+            
+            // create a new buffer for our model data
+            self.model_buffer = Buffer::new(gl::ARRAY_BUFFER, gl::STATIC_DRAW);
+            self.model_buffer.set_data(&model_data);
+            // and create the attributes in the vertex shader
+            gl::VertexAttribPointer(0, 2, gl::FLOAT, gl::FALSE, 8, std::ptr::null()); // position
+            gl::EnableVertexAttribArray(0);
+          
+            // create a new buffer for our transform data
+            self.transform_buffer = Buffer::new(gl::ARRAY_BUFFER, gl::DYNAMIC_DRAW);
+            self.transform_buffer.set_data(&transform_data);
+            // and create the attributes in the vertex shader
+            gl::VertexAttribPointer(1, 4, gl::FLOAT, gl::FALSE, 52, std::ptr::null()); // color
+            gl::VertexAttribPointer(2, 2, gl::FLOAT, gl::FALSE, 52, 16 as *const _); // offset
+            gl::VertexAttribPointer(3, 2, gl::FLOAT, gl::FALSE, 52, 24 as *const _); // scale
+            gl::VertexAttribDivisor(1, 1);
+            gl::VertexAttribDivisor(2, 1);
+            gl::VertexAttribDivisor(3, 1);
+            gl::EnableVertexAttribArray(1);
+            gl::EnableVertexAttribArray(2);
+            gl::EnableVertexAttribArray(3);
+            gl::VertexAttribPointer(4, 3, gl::FLOAT, gl::FALSE, 52, 32 as *const _); // rotation
+            gl::VertexAttribPointer(5, 2, gl::FLOAT, gl::FALSE, 52, 44 as *const _); // instance scale
+            gl::VertexAttribDivisor(4, 1);
+            gl::VertexAttribDivisor(5, 1);
+            gl::EnableVertexAttribArray(4);
+            gl::EnableVertexAttribArray(5);
+        }
+
+        self.state = ObjectState::Ok;
+        Ok(())
+    }
+
+    // resets the transformation data
+    fn reload(&mut self) {
+        let transform_data = self.transform_data.concat();
+        self.transform_buffer.set_data(&transform_data);
+        self.state = ObjectState::Ok;
+    }
+
+    // draw the mesh to the screen
+    fn draw(&mut self, draw: &Draw, camera: &Transform, model_transform: &Transform) -> Result<(), String> {
+        // reset the transformation data if needed
+        match self.state {
+            ObjectState::Reload => self.reload(),
+            ObjectState::Ok => (),
+        }
+
+        // create the mvp (model view projection) matrixes
+        let projection = mvp::ortho(&draw.window);
+        let view = camera.matrix();
+        let model = model_transform.matrix();
+
+        // a material recompiles its program when it is reloaded
+        if self.material.is_some() {
+            self.program = self.program()?;
+        }
+
+        unsafe {
+            // bind the programm and vertex array before sending
+            // uniform and drawing
+            self.program.bind();
+            self.vertex_array.bind();
+           
+            // set the model view matrices
+            self.program.set_matrices(&projection, &view, &model);
+            if let Some(material) = &self.material {
+                material.bind(&self.program)?;
+            }
+            gl::DrawArraysInstanced(gl::TRIANGLES, 0, self.vertices.len() as i32, self.transform_data.len() as i32);
+        }
+
+        Ok(())
+    }
+
+    // set the state of the Object
+    fn set_state(&mut self, object_state: ObjectState) {
+        self.state = object_state;
+    }
+
+    // a SpriteBatch only draws quads
+    fn batch_kind(&self) -> Option<BatchKind> {
+        None
+    }
+
+    // draw the object with a material or its own program again
+    fn set_material(&mut self, material: Option<Material>) -> Result<(), String> {
+        self.material = material;
+        self.program = self.program()?;
+        Ok(())
+    }
+}
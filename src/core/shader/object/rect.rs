@@ -1,40 +1,118 @@
-use crate::core::shader::object::{VertexArray, Buffer, ObjectState, Object};
-use crate::core::shader::program::Program;
-use crate::core::shader::data::ObjectData;
+use std::rc::Rc;
+use std::sync::LazyLock;
+use crate::core::shader::object::{VertexArray, Buffer, ObjectState, Object, splice_instance_transform};
+use crate::core::shader::batch::BatchKind;
+use crate::core::shader::material::Material;
+use crate::core::shader::cache::{self, ProgramKind, SharedProgram};
+use crate::core::shader::data::{ObjectData, Gradient, Shadow};
+use crate::core::color::Color;
 use crate::core::math::mvp;
 use crate::core::project::Draw;
 use crate::core::math::transform::Transform;
-use crate::core::shader::Shader;
 
-const VERTEX_SHADER_SOURCE: &str = r#"
+static VERTEX_SHADER_SOURCE: LazyLock<String> = LazyLock::new(|| splice_instance_transform(r#"
     #version 330
     layout (location = 0) in vec2 position;
     layout (location = 1) in vec4 color;
     layout (location = 2) in vec2 offset;
     layout (location = 3) in vec2 scale;
+    layout (location = 4) in vec4 style; // radius, border, gradient type, gradient angle
+    layout (location = 5) in vec4 border_color;
+    layout (location = 6) in vec4 gradient_color;
+    layout (location = 7) in vec4 shadow; // offset x, offset y, blur
+    layout (location = 8) in vec4 shadow_color;
+    layout (location = 9) in vec3 rotation; // angle, pivot x, pivot y
+    layout (location = 10) in vec2 instance_scale; // negative when flipped
     
     uniform mat4 projection;
     uniform mat4 view;
     uniform mat4 model;
 
     out vec4 oColor;
+    out vec2 oTexCoord; // for fragment materials
+    out vec2 oPosition; // for fragment materials
+    out vec2 oLocal;
+    flat out vec2 oSize;
+    flat out vec4 oStyle;
+    flat out vec4 oBorderColor;
+    flat out vec4 oGradientColor;
+    flat out vec4 oShadow;
+    flat out vec4 oShadowColor;
+
+    // INSTANCE_TRANSFORM
 
     void main() {
-        vec2 scale_position = position * scale;
-        vec2 offset_position = scale_position + offset;
-        gl_Position = projection * view * model * vec4(offset_position, 0.0, 1.0);
+        // grow the quad so the shadow fits in
+        float margin = shadow_color.a > 0.0 ? shadow.z + max(abs(shadow.x), abs(shadow.y)) : 0.0;
+        vec2 local = position * (scale + 2.0 * margin) - margin;
+        gl_Position = projection * view * model * vec4(instance(local, scale) + offset, 0.0, 1.0);
         oColor = color;
+        oPosition = local / max(scale, vec2(0.0001));
+        oTexCoord = vec2(oPosition.x, 1.0 - oPosition.y);
+        oLocal = local;
+        oSize = scale;
+        oStyle = style;
+        oBorderColor = border_color;
+        oGradientColor = gradient_color;
+        oShadow = shadow;
+        oShadowColor = shadow_color;
     }
-"#;
+"#));
 
 const FRAGMENT_SHADER_SOURCE: &str = r#"
     #version 330
     in vec4 oColor;
+    in vec2 oLocal;
+    flat in vec2 oSize;
+    flat in vec4 oStyle;
+    flat in vec4 oBorderColor;
+    flat in vec4 oGradientColor;
+    flat in vec4 oShadow;
+    flat in vec4 oShadowColor;
 
     out vec4 color;
 
+    // the signed distance to a rounded box around the origin
+    float rounded_box(vec2 p, vec2 half_size, float radius) {
+        vec2 q = abs(p) - half_size + radius;
+        return length(max(q, 0.0)) + min(max(q.x, q.y), 0.0) - radius;
+    }
+
     void main() {
-        color = vec4(oColor);
+        vec2 p = oLocal - oSize * 0.5;
+        float radius = min(oStyle.x, min(oSize.x, oSize.y) * 0.5);
+        float d = rounded_box(p, oSize * 0.5, radius);
+        // half a pixel, so the edges of plain rects stay sharp
+        float aa = max(fwidth(d) * 0.5, 0.0001);
+
+        vec4 fill = vec4(oColor.rgb, 1.0);
+        vec2 uv = oLocal / max(oSize, vec2(0.0001)) - 0.5;
+        if(oStyle.z > 1.5) {
+            // radial from the center to the corners
+            fill = mix(fill, oGradientColor, clamp(length(uv) * 1.41421, 0.0, 1.0));
+        } else if(oStyle.z > 0.5) {
+            vec2 direction = vec2(cos(oStyle.w), sin(oStyle.w));
+            float t = dot(uv, direction) / (abs(direction.x) + abs(direction.y)) + 0.5;
+            fill = mix(fill, oGradientColor, clamp(t, 0.0, 1.0));
+        }
+
+        if(oStyle.y > 0.0) {
+            float inner = 1.0 - smoothstep(-aa, aa, d + oStyle.y);
+            fill = mix(oBorderColor, fill, inner);
+        }
+
+        float shape = (1.0 - smoothstep(-aa, aa, d)) * fill.a * oColor.a;
+        color = vec4(fill.rgb, shape);
+
+        if(oShadowColor.a > 0.0) {
+            // draw the shape over the shadow
+            float shadow_distance = rounded_box(p - oShadow.xy, oSize * 0.5, radius);
+            float blur = max(oShadow.z, aa);
+            float s = (1.0 - smoothstep(-blur, blur, shadow_distance)) * oShadowColor.a * oColor.a;
+            float a = shape + s * (1.0 - shape);
+            vec3 rgb = (fill.rgb * shape + oShadowColor.rgb * s * (1.0 - shape)) / max(a, 0.0001);
+            color = vec4(rgb, a);
+        }
     }
 "#;
 
@@ -47,27 +125,67 @@ the screen
 when rect gets dropped the shader also gets 
 deletet from the graphics card
 */
-type TransformData = [f32; 8];
+type TransformData = [f32; 33];
+
+// color, offset, scale, the style and the instance transform of the rect
+fn transform_data(object_data: &ObjectData) -> TransformData {
+    let color = object_data.color;
+    let opacity = object_data.opacity;
+    let (offset_x, offset_y) = object_data.offset;
+    let (width, height) = object_data.dim;
+    let border = object_data.border_color;
+
+    let (gradient_type, gradient_angle, gradient) = match object_data.gradient {
+        Gradient::None => (0.0, 0.0, Color::default()),
+        Gradient::Linear {color, angle} => (1.0, angle, color),
+        Gradient::Radial {color} => (2.0, 0.0, color),
+    };
+
+    // a shadow without color is not drawn
+    let shadow = object_data.shadow.unwrap_or(Shadow::new(0.0, 0.0, 0.0, &Color {r: 0.0, g: 0.0, b: 0.0, a: 0.0}));
+
+    let [rotation, pivot_x, pivot_y, x_scale, y_scale] = object_data.instance_data();
+
+    [
+        color.r, color.g, color.b, opacity, offset_x, offset_y, width, height,
+        object_data.radius, object_data.border, gradient_type, gradient_angle,
+        border.r, border.g, border.b, border.a,
+        gradient.r, gradient.g, gradient.b, gradient.a,
+        shadow.offset.0, shadow.offset.1, shadow.blur, 0.0,
+        shadow.color.r, shadow.color.g, shadow.color.b, shadow.color.a,
+        rotation, pivot_x, pivot_y, x_scale, y_scale,
+    ]
+}
 
 pub struct Rect {
-    program: Program,
+    program: Rc<SharedProgram>,
     vertex_array: VertexArray,
     model_buffer: Buffer, // the buffer needs to stay alive
     transform_buffer: Buffer, // the buffer needs to stay alive
     transform_data: Vec<TransformData>,
     state: ObjectState,
+    material: Option<Material>,
 }
 
 impl Rect {
     // creates an empty Rect
     pub fn new() -> Self {
         Self {
-            program: Program::default(),
+            program: Rc::new(SharedProgram::default()),
             vertex_array: VertexArray::default(),
             model_buffer: Buffer::default(),
             transform_buffer: Buffer::default(),
             transform_data: vec![],
             state: ObjectState::Ok,
+            material: None,
+        }
+    }
+
+    // the program of the material or the shared program of the object
+    fn program(&self) -> Result<Rc<SharedProgram>, String> {
+        match &self.material {
+            Some(material) => material.program(ProgramKind::Rect, &VERTEX_SHADER_SOURCE),
+            None => cache::get(ProgramKind::Rect, &VERTEX_SHADER_SOURCE, FRAGMENT_SHADER_SOURCE),
         }
     }
 }
@@ -75,31 +193,13 @@ impl Rect {
 impl Object for Rect {
     // add an new Rect to the transform data
     fn add(&mut self, object_data: &ObjectData) {
-        let color = object_data.color;
-        let opacity = object_data.opacity;
-        let (offset_x, offset_y) = object_data.offset;
-        let (width, height) = object_data.dim;
-
-        let transform_data: TransformData = [
-            color.r, color.g, color.b, opacity, offset_x, offset_y, width, height, 
-        ];
-
-        self.transform_data.push(transform_data);
+        self.transform_data.push(transform_data(object_data));
     }
 
     /// set the component data
     /// for a specific element 'i' of the transform data vector
     fn set(&mut self, i: usize, object_data: &ObjectData) {
-        let color = object_data.color;
-        let opacity = object_data.opacity;
-        let (offset_x, offset_y) = object_data.offset;
-        let (width, height) = object_data.dim;
-
-        let transform_data: TransformData = [
-            color.r, color.g, color.b, opacity, offset_x, offset_y, width, height, 
-        ];
-
-        self.transform_data[i] = transform_data;
+        self.transform_data[i] = transform_data(object_data);
     }
 
     // removes a rect from 
@@ -127,10 +227,8 @@ impl Object for Rect {
         let transform_data = self.transform_data.concat();
 
         unsafe {
-            // create the shaderprogram
-            let vertex_shader = Shader::new(VERTEX_SHADER_SOURCE, gl::VERTEX_SHADER)?;
-            let fragment_shader = Shader::new(FRAGMENT_SHADER_SOURCE, gl::FRAGMENT_SHADER)?;
-            self.program = Program::new(&vertex_shader, &fragment_shader)?;
+            // get the program of the material or the shared shaderprogram
+            self.program = self.program()?;
 
             // create a new buffer for our vertex array (model + transform data)
             self.vertex_array = VertexArray::new();
@@ -147,15 +245,20 @@ impl Object for Rect {
             self.transform_buffer = Buffer::new(gl::ARRAY_BUFFER, gl::DYNAMIC_DRAW);
             self.transform_buffer.set_data(&transform_data);
             // and create the attributes in the vertex shader
-            gl::VertexAttribPointer(1, 4, gl::FLOAT, gl::FALSE, 32, 0 as *const _); // color
-            gl::VertexAttribPointer(2, 2, gl::FLOAT, gl::FALSE, 32, 16 as *const _); // offset
-            gl::VertexAttribPointer(3, 2, gl::FLOAT, gl::FALSE, 32, 24 as *const _); // scale
-            gl::VertexAttribDivisor(1, 1);
-            gl::VertexAttribDivisor(2, 1);
-            gl::VertexAttribDivisor(3, 1);
-            gl::EnableVertexAttribArray(1);
-            gl::EnableVertexAttribArray(2);
-            gl::EnableVertexAttribArray(3);
+            gl::VertexAttribPointer(1, 4, gl::FLOAT, gl::FALSE, 132, 0 as *const _); // color
+            gl::VertexAttribPointer(2, 2, gl::FLOAT, gl::FALSE, 132, 16 as *const _); // offset
+            gl::VertexAttribPointer(3, 2, gl::FLOAT, gl::FALSE, 132, 24 as *const _); // scale
+            gl::VertexAttribPointer(4, 4, gl::FLOAT, gl::FALSE, 132, 32 as *const _); // style
+            gl::VertexAttribPointer(5, 4, gl::FLOAT, gl::FALSE, 132, 48 as *const _); // border color
+            gl::VertexAttribPointer(6, 4, gl::FLOAT, gl::FALSE, 132, 64 as *const _); // gradient color
+            gl::VertexAttribPointer(7, 4, gl::FLOAT, gl::FALSE, 132, 80 as *const _); // shadow
+            gl::VertexAttribPointer(8, 4, gl::FLOAT, gl::FALSE, 132, 96 as *const _); // shadow color
+            gl::VertexAttribPointer(9, 3, gl::FLOAT, gl::FALSE, 132, 112 as *const _); // rotation
+            gl::VertexAttribPointer(10, 2, gl::FLOAT, gl::FALSE, 132, 124 as *const _); // instance scale
+            for location in 1..11 {
+                gl::VertexAttribDivisor(location, 1);
+                gl::EnableVertexAttribArray(location);
+            }
         }
 
         self.state = ObjectState::Ok;
@@ -182,6 +285,11 @@ impl Object for Rect {
         let view = camera.matrix();
         let model = model_transform.matrix();
 
+        // a material recompiles its program when it is reloaded
+        if self.material.is_some() {
+            self.program = self.program()?;
+        }
+
         unsafe {
             // bind the programm and vertex array before sending
             // uniform and drawing
@@ -189,12 +297,10 @@ impl Object for Rect {
             self.vertex_array.bind();
            
             // set the model view matrices
-            let projection_location = self.program.get_uniform_location("projection")?;
-            let view_location = self.program.get_uniform_location("view")?;
-            let model_location = self.program.get_uniform_location("model")?;
-            gl::UniformMatrix4fv(projection_location, 1, gl::FALSE, projection.as_ptr());
-            gl::UniformMatrix4fv(view_location, 1, gl::FALSE, view.as_ptr());
-            gl::UniformMatrix4fv(model_location, 1, gl::FALSE, model.as_ptr());
+            self.program.set_matrices(&projection, &view, &model);
+            if let Some(material) = &self.material {
+                material.bind(&self.program)?;
+            }
             gl::DrawArraysInstanced(gl::TRIANGLE_FAN, 0, 4, self.transform_data.len() as i32);
         }
 
@@ -205,4 +311,51 @@ impl Object for Rect {
     fn set_state(&mut self, object_state: ObjectState) {
         self.state = object_state;
     }
-}
\ No newline at end of file
+
+    // how the object is drawn by a SpriteBatch
+    fn batch_kind(&self) -> Option<BatchKind> {
+        Some(BatchKind::Rect)
+    }
+
+    // draw the object with a material or its own program again
+    fn set_material(&mut self, material: Option<Material>) -> Result<(), String> {
+        self.material = material;
+        self.program = self.program()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_instance_transform() {
+        let source: &str = &VERTEX_SHADER_SOURCE;
+        assert!(source.contains(crate::core::shader::object::INSTANCE_TRANSFORM));
+        assert!(!source.contains("// INSTANCE_TRANSFORM"));
+    }
+
+    #[test]
+    fn test_transform_data() {
+        let plain = transform_data(&ObjectData::default());
+        // a plain rect has no style and no shadow
+        assert!(plain[8..28].iter().all(|v| *v == 0.0));
+
+        let object_data = ObjectData {
+            radius: 4.0,
+            border: 2.0,
+            gradient: Gradient::Linear {color: Color::rgb(255, 0, 0), angle: 1.5},
+            shadow: Some(Shadow::new(3.0, 5.0, 8.0, &Color::grey(0))),
+            ..ObjectData::default()
+        };
+
+        let styled = transform_data(&object_data);
+        assert_eq!([4.0, 2.0, 1.0, 1.5], styled[8..12]);
+        assert_eq!([1.0, 0.0, 0.0, 1.0], styled[16..20]);
+        assert_eq!([3.0, 5.0, 8.0, 0.0], styled[20..24]);
+        assert_eq!(1.0, styled[27]);
+        // no rotation, centered pivot and no scale
+        assert_eq!([0.0, 0.5, 0.5, 1.0, 1.0], styled[28..]);
+    }
+}
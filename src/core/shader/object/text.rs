@@ -1,19 +1,24 @@
-use crate::core::shader::object::{VertexArray, Buffer, ObjectState, Object, TextureBuffer};
-use crate::core::shader::program::Program;
+use std::rc::Rc;
+use std::sync::LazyLock;
+use crate::core::shader::object::{VertexArray, Buffer, ObjectState, Object, TextureBuffer, splice_instance_transform};
+use crate::core::shader::batch::BatchKind;
+use crate::core::shader::material::Material;
+use crate::core::shader::cache::{self, ProgramKind, SharedProgram};
 use crate::core::resource::image::Image;
 use crate::core::shader::data::ObjectData;
 use crate::core::math::mvp;
 use crate::core::project::Draw;
 use crate::core::math::transform::Transform;
-use crate::core::shader::Shader;
 
-const VERTEX_SHADER_SOURCE: &str = r#"
+static VERTEX_SHADER_SOURCE: LazyLock<String> = LazyLock::new(|| splice_instance_transform(r#"
     #version 330
     layout (location = 0) in vec2 position;
     layout (location = 1) in vec2 texcoord;
     layout (location = 2) in vec2 offset;
     layout (location = 3) in vec2 scale;
     layout (location = 4) in vec4 color;
+    layout (location = 5) in vec3 rotation; // angle, pivot x, pivot y
+    layout (location = 6) in vec2 instance_scale; // negative when flipped
     
     uniform mat4 projection;
     uniform mat4 view;
@@ -21,15 +26,19 @@ const VERTEX_SHADER_SOURCE: &str = r#"
 
     out vec2 oTexCoord;
     out vec4 oColor;
+    out vec2 oPosition; // for fragment materials
    
+    // INSTANCE_TRANSFORM
+
     void main() {
         vec2 scale_position = position * scale;
-        vec2 offset_position = scale_position + offset;
+        vec2 offset_position = instance(scale_position, scale) + offset;
         gl_Position = projection * view * model * vec4(offset_position, 0.0, 1.0);
         oTexCoord = texcoord;
         oColor = color;
+        oPosition = position;
     }
-"#;
+"#));
 
 const FRAGMENT_SHADER_SOURCE: &str = r#"
     #version 330
@@ -51,34 +60,44 @@ const FRAGMENT_SHADER_SOURCE: &str = r#"
     }
 "#;
 
-type TransformData = [f32; 8];
+type TransformData = [f32; 13];
 
 pub struct Text {
-    program: Program,
+    program: Rc<SharedProgram>,
     vertex_array: VertexArray,
     model_buffer: Buffer, // the buffer needs to stay alive
-    texture_buffer: TextureBuffer, // the buffer needs to stay alive
+    texture_buffer: Rc<TextureBuffer>, // shared with a SpriteBatch until it is flushed
     transform_buffer: Buffer, // the buffer needs to stay alive
     transform_data: Vec<TransformData>,
     image_data: Image,
     state: ObjectState,
+    material: Option<Material>,
 }
 
 impl Text {
     pub fn new(image: &Image) -> Result<Self, String> {
         let text = Self {
-            program: Program::default(),
+            program: Rc::new(SharedProgram::default()),
             vertex_array: VertexArray::default(),
             model_buffer: Buffer::default(),
-            texture_buffer: TextureBuffer::default(),
+            texture_buffer: Rc::new(TextureBuffer::default()),
             transform_buffer: Buffer::default(),
             transform_data: vec![],
             image_data: image.clone(),
             state: ObjectState::Ok,
+            material: None,
         };
 
         Ok(text)
     }
+
+    // the program of the material or the shared program of the object
+    fn program(&self) -> Result<Rc<SharedProgram>, String> {
+        match &self.material {
+            Some(material) => material.program(ProgramKind::Text, &VERTEX_SHADER_SOURCE),
+            None => cache::get(ProgramKind::Text, &VERTEX_SHADER_SOURCE, FRAGMENT_SHADER_SOURCE),
+        }
+    }
 }
 
 impl Object for Text {
@@ -89,8 +108,11 @@ impl Object for Text {
         let color = object_data.color; 
         let opacity = object_data.opacity;
 
+        let [rotation, pivot_x, pivot_y, x_scale, y_scale] = object_data.instance_data();
+
         let transform_data: TransformData = [
-            x_offset, y_offset, width, height, color.r, color.g, color.b, opacity
+            x_offset, y_offset, width, height, color.r, color.g, color.b, opacity,
+            rotation, pivot_x, pivot_y, x_scale, y_scale,
         ];
 
         self.transform_data.push(transform_data);
@@ -104,8 +126,11 @@ impl Object for Text {
         let color = object_data.color; 
         let opacity = object_data.opacity;
 
+        let [rotation, pivot_x, pivot_y, x_scale, y_scale] = object_data.instance_data();
+
         let transform_data: TransformData = [
-            x_offset, y_offset, width, height, color.r, color.g, color.b, opacity
+            x_offset, y_offset, width, height, color.r, color.g, color.b, opacity,
+            rotation, pivot_x, pivot_y, x_scale, y_scale,
         ];
 
         self.transform_data[i] = transform_data;
@@ -136,10 +161,8 @@ impl Object for Text {
         let transform_data = self.transform_data.concat();
         
         unsafe {
-            // create the shaderprogram
-            let vertex_shader = Shader::new(VERTEX_SHADER_SOURCE, gl::VERTEX_SHADER)?;
-            let fragment_shader = Shader::new(FRAGMENT_SHADER_SOURCE, gl::FRAGMENT_SHADER)?;
-            self.program = Program::new(&vertex_shader, &fragment_shader)?;
+            // get the program of the material or the shared shaderprogram
+            self.program = self.program()?;
 
             // create a new buffer for our vertex array (model + transform data)
             self.vertex_array = VertexArray::new();
@@ -155,22 +178,29 @@ impl Object for Text {
             gl::EnableVertexAttribArray(1);
 
             // create the texture buffer out of the image
-            self.texture_buffer = TextureBuffer::new();
-            self.texture_buffer.set_data(&self.image_data.to_rgba_image());
+            let texture_buffer = TextureBuffer::new();
+            texture_buffer.set_data(&self.image_data.to_rgba_image());
+            self.texture_buffer = Rc::new(texture_buffer);
           
             // create a new buffer for our transform data
             self.transform_buffer = Buffer::new(gl::ARRAY_BUFFER, gl::DYNAMIC_DRAW);
             self.transform_buffer.set_data(&transform_data);
             // and create the attributes in the vertex shader
-            gl::VertexAttribPointer(2, 2, gl::FLOAT, gl::FALSE, 32, 0 as *const _); // offset
-            gl::VertexAttribPointer(3, 2, gl::FLOAT, gl::FALSE, 32, 8 as *const _); // scale
-            gl::VertexAttribPointer(4, 4, gl::FLOAT, gl::FALSE, 32, 16 as *const _); // color
+            gl::VertexAttribPointer(2, 2, gl::FLOAT, gl::FALSE, 52, 0 as *const _); // offset
+            gl::VertexAttribPointer(3, 2, gl::FLOAT, gl::FALSE, 52, 8 as *const _); // scale
+            gl::VertexAttribPointer(4, 4, gl::FLOAT, gl::FALSE, 52, 16 as *const _); // color
             gl::VertexAttribDivisor(2, 1);
             gl::VertexAttribDivisor(3, 1);
             gl::VertexAttribDivisor(4, 1);
             gl::EnableVertexAttribArray(2);
             gl::EnableVertexAttribArray(3);
             gl::EnableVertexAttribArray(4);
+            gl::VertexAttribPointer(5, 3, gl::FLOAT, gl::FALSE, 52, 32 as *const _); // rotation
+            gl::VertexAttribPointer(6, 2, gl::FLOAT, gl::FALSE, 52, 44 as *const _); // instance scale
+            gl::VertexAttribDivisor(5, 1);
+            gl::VertexAttribDivisor(6, 1);
+            gl::EnableVertexAttribArray(5);
+            gl::EnableVertexAttribArray(6);
         }
 
         self.state = ObjectState::Ok;
@@ -196,6 +226,11 @@ impl Object for Text {
         let view = camera.matrix();
         let model = model_transform.matrix();
 
+        // a material recompiles its program when it is reloaded
+        if self.material.is_some() {
+            self.program = self.program()?;
+        }
+
         unsafe {
             // bind the programm and vertex array before sending
             // uniform and drawing
@@ -205,12 +240,10 @@ impl Object for Text {
             self.texture_buffer.bind();
 
             // set the model view matrices
-            let projection_location = self.program.get_uniform_location("projection")?;
-            let view_location = self.program.get_uniform_location("view")?;
-            let model_location = self.program.get_uniform_location("model")?;
-            gl::UniformMatrix4fv(projection_location, 1, gl::FALSE, projection.as_ptr());
-            gl::UniformMatrix4fv(view_location, 1, gl::FALSE, view.as_ptr());
-            gl::UniformMatrix4fv(model_location, 1, gl::FALSE, model.as_ptr());
+            self.program.set_matrices(&projection, &view, &model);
+            if let Some(material) = &self.material {
+                material.bind(&self.program)?;
+            }
             gl::DrawArraysInstanced(gl::TRIANGLE_FAN, 0, 4, self.transform_data.len() as i32);
         }
 
@@ -221,4 +254,16 @@ impl Object for Text {
     fn set_state(&mut self, object_state: ObjectState) {
         self.state = object_state;
     }
-}
\ No newline at end of file
+
+    // how the object is drawn by a SpriteBatch
+    fn batch_kind(&self) -> Option<BatchKind> {
+        Some(BatchKind::Text(self.texture_buffer.clone()))
+    }
+
+    // draw the object with a material or its own program again
+    fn set_material(&mut self, material: Option<Material>) -> Result<(), String> {
+        self.material = material;
+        self.program = self.program()?;
+        Ok(())
+    }
+}
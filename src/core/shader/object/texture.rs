@@ -1,13 +1,16 @@
-use crate::core::shader::object::{VertexArray, Buffer, ObjectState, Object, TextureBuffer};
-use crate::core::shader::program::Program;
+use std::rc::Rc;
+use std::sync::LazyLock;
+use crate::core::shader::object::{VertexArray, Buffer, ObjectState, Object, TextureBuffer, splice_instance_transform};
+use crate::core::shader::batch::BatchKind;
+use crate::core::shader::material::Material;
+use crate::core::shader::cache::{self, ProgramKind, SharedProgram};
 use crate::core::resource::image::Image;
 use crate::core::shader::data::ObjectData;
 use crate::core::math::mvp;
 use crate::core::project::Draw;
 use crate::core::math::transform::Transform;
-use crate::core::shader::Shader;
 
-const VERTEX_SHADER_SOURCE: &str = r#"
+static VERTEX_SHADER_SOURCE: LazyLock<String> = LazyLock::new(|| splice_instance_transform(r#"
     #version 330
     layout (location = 0) in vec2 position;
     layout (location = 1) in vec2 offset;
@@ -15,19 +18,25 @@ const VERTEX_SHADER_SOURCE: &str = r#"
     layout (location = 3) in float opacity;
     layout (location = 4) in vec4 texcoord_1;
     layout (location = 5) in vec4 texcoord_2;
+    layout (location = 6) in vec3 rotation; // angle, pivot x, pivot y
+    layout (location = 7) in vec2 instance_scale; // negative when flipped
     
     uniform mat4 projection;
     uniform mat4 view;
     uniform mat4 model;
 
     out vec2 oTexCoord;
-    out float oOpacity;
+    out vec4 oColor; // white with the opacity
+    out vec2 oPosition; // for fragment materials
+
+    // INSTANCE_TRANSFORM
 
     void main() {
         vec2 scale_position = position * scale;
-        vec2 offset_position = scale_position + offset;
+        vec2 offset_position = instance(scale_position, scale) + offset;
         gl_Position = projection * view * model * vec4(offset_position, 0.0, 1.0);
-        oOpacity = opacity;
+        oColor = vec4(1.0, 1.0, 1.0, opacity);
+        oPosition = position;
 
         float tex_correction = 0.000001;
 
@@ -41,7 +50,7 @@ const VERTEX_SHADER_SOURCE: &str = r#"
             oTexCoord = vec2(texcoord_2.z - tex_correction, texcoord_2.w + tex_correction);
         }
     }
-"#;
+"#));
 
 const FRAGMENT_SHADER_SOURCE: &str = r#"
     #version 330
@@ -49,42 +58,67 @@ const FRAGMENT_SHADER_SOURCE: &str = r#"
     uniform sampler2D sampler;
 
     in vec2 oTexCoord;
-    in float oOpacity;
+    in vec4 oColor;
 
     out vec4 color;
 
     void main() {
         vec4 t = texture(sampler, oTexCoord);
-        t.a = t.a * oOpacity;
+        t.a = t.a * oColor.a;
         color = t;
     }
 "#;
 
-type TransformData = [f32; 13];
+type TransformData = [f32; 18];
 
 pub struct Texture {
-    program: Program,
+    program: Rc<SharedProgram>,
     vertex_array: VertexArray,
     model_buffer: Buffer, // the buffer needs to stay alive
-    texture_buffer: TextureBuffer, // the buffer needs to stay alive
+    texture_buffer: Rc<TextureBuffer>, // can be shared with a render target or a SpriteBatch
     transform_buffer: Buffer, // the buffer needs to stay alive
     transform_data: Vec<TransformData>,
-    image: Image,
+    image: Option<Image>, // uploaded to the texture buffer on load
     state: ObjectState,
+    material: Option<Material>,
 }
 
 impl Texture {
     // creates an empty Texture
     pub fn new(image: &Image) -> Self {
         Self {
-            program: Program::default(),
+            program: Rc::new(SharedProgram::default()),
+            vertex_array: VertexArray::default(),
+            model_buffer: Buffer::default(),
+            texture_buffer: Rc::new(TextureBuffer::default()),
+            transform_buffer: Buffer::default(),
+            transform_data: vec![],
+            image: Some(image.clone()),
+            state: ObjectState::Ok,
+            material: None,
+        }
+    }
+
+    // creates an empty Texture that draws an existing texture buffer
+    pub fn from_buffer(texture_buffer: Rc<TextureBuffer>) -> Self {
+        Self {
+            program: Rc::new(SharedProgram::default()),
             vertex_array: VertexArray::default(),
             model_buffer: Buffer::default(),
-            texture_buffer: TextureBuffer::default(),
+            texture_buffer,
             transform_buffer: Buffer::default(),
             transform_data: vec![],
-            image: image.clone(),
+            image: None,
             state: ObjectState::Ok,
+            material: None,
+        }
+    }
+
+    // the program of the material or the shared program of the object
+    fn program(&self) -> Result<Rc<SharedProgram>, String> {
+        match &self.material {
+            Some(material) => material.program(ProgramKind::Texture, &VERTEX_SHADER_SOURCE),
+            None => cache::get(ProgramKind::Texture, &VERTEX_SHADER_SOURCE, FRAGMENT_SHADER_SOURCE),
         }
     }
 }
@@ -97,10 +131,13 @@ impl Object for Texture {
         let (width, height) = object_data.dim;
         let opacity = object_data.opacity;
 
+        let [rotation, pivot_x, pivot_y, x_scale, y_scale] = object_data.instance_data();
+
         let transform_data: TransformData = [
             x_offset, y_offset, width, height, opacity, 
             texcoord[0], texcoord[1], texcoord[2], texcoord[3],
-            texcoord[4], texcoord[5], texcoord[6], texcoord[7], 
+            texcoord[4], texcoord[5], texcoord[6], texcoord[7],
+            rotation, pivot_x, pivot_y, x_scale, y_scale,
         ];
 
         self.transform_data.push(transform_data);
@@ -114,10 +151,13 @@ impl Object for Texture {
         let (width, height) = object_data.dim;
         let opacity = object_data.opacity;
 
+        let [rotation, pivot_x, pivot_y, x_scale, y_scale] = object_data.instance_data();
+
         let transform_data: TransformData = [
             x_offset, y_offset, width, height, opacity, 
             texcoord[0], texcoord[1], texcoord[2], texcoord[3],
-            texcoord[4], texcoord[5], texcoord[6], texcoord[7], 
+            texcoord[4], texcoord[5], texcoord[6], texcoord[7],
+            rotation, pivot_x, pivot_y, x_scale, y_scale,
         ];
 
         self.transform_data[i] = transform_data;
@@ -148,10 +188,8 @@ impl Object for Texture {
         let transform_data = self.transform_data.concat();
         
         unsafe {
-            // create the shaderprogram
-            let vertex_shader = Shader::new(VERTEX_SHADER_SOURCE, gl::VERTEX_SHADER)?;
-            let fragment_shader = Shader::new(FRAGMENT_SHADER_SOURCE, gl::FRAGMENT_SHADER)?;
-            self.program = Program::new(&vertex_shader, &fragment_shader)?;
+            // get the program of the material or the shared shaderprogram
+            self.program = self.program()?;
 
             // create a new buffer for our vertex array (model + transform data)
             self.vertex_array = VertexArray::new();
@@ -164,19 +202,23 @@ impl Object for Texture {
             gl::VertexAttribPointer(0, 2, gl::FLOAT, gl::FALSE, 8, 0 as *const _); // position
             gl::EnableVertexAttribArray(0);
 
-            // create the texture buffer out of the image
-            self.texture_buffer = TextureBuffer::new();
-            self.texture_buffer.set_data(&self.image.to_rgba_image());
+            // create the texture buffer out of the image,
+            // a shared texture buffer already has its data
+            if let Some(image) = &self.image {
+                let texture_buffer = TextureBuffer::new();
+                texture_buffer.set_data(&image.to_rgba_image());
+                self.texture_buffer = Rc::new(texture_buffer);
+            }
           
             // create a new buffer for our transform data
             self.transform_buffer = Buffer::new(gl::ARRAY_BUFFER, gl::DYNAMIC_DRAW);
             self.transform_buffer.set_data(&transform_data);
             // and create the attributes in the vertex shader
-            gl::VertexAttribPointer(1, 2, gl::FLOAT, gl::FALSE, 52, 0 as *const _); // offset
-            gl::VertexAttribPointer(2, 2, gl::FLOAT, gl::FALSE, 52, 8 as *const _); // scale
-            gl::VertexAttribPointer(3, 1, gl::FLOAT, gl::FALSE, 52, 16 as *const _); // opacity
-            gl::VertexAttribPointer(4, 4, gl::FLOAT, gl::FALSE, 52, 20 as *const _); // texcoord_1
-            gl::VertexAttribPointer(5, 4, gl::FLOAT, gl::FALSE, 52, 36 as *const _); // texcoord_2
+            gl::VertexAttribPointer(1, 2, gl::FLOAT, gl::FALSE, 72, 0 as *const _); // offset
+            gl::VertexAttribPointer(2, 2, gl::FLOAT, gl::FALSE, 72, 8 as *const _); // scale
+            gl::VertexAttribPointer(3, 1, gl::FLOAT, gl::FALSE, 72, 16 as *const _); // opacity
+            gl::VertexAttribPointer(4, 4, gl::FLOAT, gl::FALSE, 72, 20 as *const _); // texcoord_1
+            gl::VertexAttribPointer(5, 4, gl::FLOAT, gl::FALSE, 72, 36 as *const _); // texcoord_2
             gl::VertexAttribDivisor(1, 1);
             gl::VertexAttribDivisor(2, 1);
             gl::VertexAttribDivisor(3, 1);
@@ -187,6 +229,12 @@ impl Object for Texture {
             gl::EnableVertexAttribArray(3);
             gl::EnableVertexAttribArray(4);
             gl::EnableVertexAttribArray(5);
+            gl::VertexAttribPointer(6, 3, gl::FLOAT, gl::FALSE, 72, 52 as *const _); // rotation
+            gl::VertexAttribPointer(7, 2, gl::FLOAT, gl::FALSE, 72, 64 as *const _); // instance scale
+            gl::VertexAttribDivisor(6, 1);
+            gl::VertexAttribDivisor(7, 1);
+            gl::EnableVertexAttribArray(6);
+            gl::EnableVertexAttribArray(7);
         }
 
         self.state = ObjectState::Ok;
@@ -212,6 +260,11 @@ impl Object for Texture {
         let view = camera.matrix();
         let model = model_transform.matrix();
 
+        // a material recompiles its program when it is reloaded
+        if self.material.is_some() {
+            self.program = self.program()?;
+        }
+
         unsafe {
             // bind the programm and vertex array before sending
             // uniform and drawing
@@ -221,12 +274,10 @@ impl Object for Texture {
             self.texture_buffer.bind();
         
             // set the model view matrices
-            let projection_location = self.program.get_uniform_location("projection")?;
-            let view_location = self.program.get_uniform_location("view")?;
-            let model_location = self.program.get_uniform_location("model")?;
-            gl::UniformMatrix4fv(projection_location, 1, gl::FALSE, projection.as_ptr());
-            gl::UniformMatrix4fv(view_location, 1, gl::FALSE, view.as_ptr());
-            gl::UniformMatrix4fv(model_location, 1, gl::FALSE, model.as_ptr());
+            self.program.set_matrices(&projection, &view, &model);
+            if let Some(material) = &self.material {
+                material.bind(&self.program)?;
+            }
             gl::DrawArraysInstanced(gl::TRIANGLE_FAN, 0, 4, self.transform_data.len() as i32);
         }
 
@@ -237,4 +288,16 @@ impl Object for Texture {
     fn set_state(&mut self, object_state: ObjectState) {
         self.state = object_state;
     }
+
+    // how the object is drawn by a SpriteBatch
+    fn batch_kind(&self) -> Option<BatchKind> {
+        Some(BatchKind::Texture(self.texture_buffer.clone()))
+    }
+
+    // draw the object with a material or its own program again
+    fn set_material(&mut self, material: Option<Material>) -> Result<(), String> {
+        self.material = material;
+        self.program = self.program()?;
+        Ok(())
+    }
 }
@@ -0,0 +1,497 @@
+use std::collections::HashMap;
+use std::time::Instant;
+use crate::core::shader::Shader;
+use crate::core::shader::program::Program;
+use crate::core::shader::object::{VertexArray, TextureBuffer};
+use crate::core::shader::target::RenderTarget;
+use crate::core::shader::uniform::Uniform;
+use crate::core::resource::image::Image;
+use crate::core::project::Draw;
+
+// a triangle that covers the whole screen, no buffers needed
+const VERTEX_SHADER_SOURCE: &str = r#"
+    #version 330
+
+    out vec2 oTexCoord;
+
+    void main() {
+        vec2 position = vec2(float((gl_VertexID << 1) & 2), float(gl_VertexID & 2));
+        oTexCoord = position;
+        gl_Position = vec4(position * 2.0 - 1.0, 0.0, 1.0);
+    }
+"#;
+
+const COPY_SOURCE: &str = r#"
+    #version 330
+    uniform sampler2D screen;
+    in vec2 oTexCoord;
+    out vec4 color;
+
+    void main() {
+        color = vec4(texture(screen, oTexCoord).rgb, 1.0);
+    }
+"#;
+
+const GRAYSCALE_SOURCE: &str = r#"
+    #version 330
+    uniform sampler2D screen;
+    in vec2 oTexCoord;
+    out vec4 color;
+
+    void main() {
+        vec3 c = texture(screen, oTexCoord).rgb;
+        float l = dot(c, vec3(0.299, 0.587, 0.114));
+        color = vec4(vec3(l), 1.0);
+    }
+"#;
+
+const PIXELATE_SOURCE: &str = r#"
+    #version 330
+    uniform sampler2D screen;
+    uniform vec2 resolution;
+    uniform float size;
+    in vec2 oTexCoord;
+    out vec4 color;
+
+    void main() {
+        vec2 cell = max(size, 1.0) / resolution;
+        vec2 uv = (floor(oTexCoord / cell) + 0.5) * cell;
+        color = vec4(texture(screen, uv).rgb, 1.0);
+    }
+"#;
+
+const VIGNETTE_SOURCE: &str = r#"
+    #version 330
+    uniform sampler2D screen;
+    uniform float strength;
+    uniform float radius;
+    in vec2 oTexCoord;
+    out vec4 color;
+
+    void main() {
+        vec3 c = texture(screen, oTexCoord).rgb;
+        float d = distance(oTexCoord, vec2(0.5));
+        float v = 1.0 - strength * smoothstep(radius, 0.75, d);
+        color = vec4(c * v, 1.0);
+    }
+"#;
+
+const CRT_SOURCE: &str = r#"
+    #version 330
+    uniform sampler2D screen;
+    uniform float scanlines;
+    uniform float curvature;
+    in vec2 oTexCoord;
+    out vec4 color;
+
+    void main() {
+        // bend the screen like the glass of an old monitor
+        vec2 c = oTexCoord * 2.0 - 1.0;
+        c *= 1.0 + curvature * dot(c.yx, c.yx);
+        vec2 uv = c * 0.5 + 0.5;
+
+        if(uv.x < 0.0 || uv.x > 1.0 || uv.y < 0.0 || uv.y > 1.0) {
+            color = vec4(0.0, 0.0, 0.0, 1.0);
+            return;
+        }
+
+        float scan = 0.8 + 0.2 * sin(uv.y * scanlines * 6.28318);
+        color = vec4(texture(screen, uv).rgb * scan, 1.0);
+    }
+"#;
+
+const BLUR_SOURCE: &str = r#"
+    #version 330
+    uniform sampler2D screen;
+    uniform vec2 resolution;
+    uniform float radius;
+    in vec2 oTexCoord;
+    out vec4 color;
+
+    void main() {
+        vec2 texel = radius / resolution / 4.0;
+        vec3 sum = vec3(0.0);
+        float total = 0.0;
+
+        for(int x = -4; x <= 4; x++) {
+            for(int y = -4; y <= 4; y++) {
+                float w = exp(-float(x * x + y * y) / 8.0);
+                sum += texture(screen, oTexCoord + vec2(x, y) * texel).rgb * w;
+                total += w;
+            }
+        }
+
+        color = vec4(sum / total, 1.0);
+    }
+"#;
+
+const BLOOM_SOURCE: &str = r#"
+    #version 330
+    uniform sampler2D screen;
+    uniform vec2 resolution;
+    uniform float threshold;
+    uniform float intensity;
+    uniform float radius;
+    in vec2 oTexCoord;
+    out vec4 color;
+
+    void main() {
+        vec2 texel = radius / resolution / 4.0;
+        vec3 glow = vec3(0.0);
+        float total = 0.0;
+
+        // blur only the parts that are brighter than the threshold
+        for(int x = -4; x <= 4; x++) {
+            for(int y = -4; y <= 4; y++) {
+                float w = exp(-float(x * x + y * y) / 8.0);
+                vec3 c = texture(screen, oTexCoord + vec2(x, y) * texel).rgb;
+                glow += max(c - vec3(threshold), vec3(0.0)) * w;
+                total += w;
+            }
+        }
+
+        vec3 base = texture(screen, oTexCoord).rgb;
+        color = vec4(base + glow / total * intensity, 1.0);
+    }
+"#;
+
+const COLOR_GRADE_SOURCE: &str = r#"
+    #version 330
+    uniform sampler2D screen;
+    uniform sampler2D lut;
+    uniform float lut_size;
+    in vec2 oTexCoord;
+    out vec4 color;
+
+    vec3 lookup(vec3 c, float slice) {
+        float x = (c.r * (lut_size - 1.0) + 0.5) / (lut_size * lut_size) + slice / lut_size;
+        float y = 1.0 - (c.g * (lut_size - 1.0) + 0.5) / lut_size;
+        return texture(lut, vec2(x, y)).rgb;
+    }
+
+    void main() {
+        vec3 c = clamp(texture(screen, oTexCoord).rgb, 0.0, 1.0);
+        float b = c.b * (lut_size - 1.0);
+        float slice = floor(b);
+        vec3 graded = mix(lookup(c, slice), lookup(c, min(slice + 1.0, lut_size - 1.0)), b - slice);
+        color = vec4(graded, 1.0);
+    }
+"#;
+
+/**
+a full screen pass of a PostProcess
+
+every pass gets the image of the pass before as sampler2D screen,
+a custom fragment shader can also use these uniforms and inputs
+```glsl
+#version 330
+uniform sampler2D screen;
+uniform vec2 resolution; // in pixels
+uniform float time; // in seconds since the PostProcess was created
+in vec2 oTexCoord;
+out vec4 color;
+```
+*/
+pub enum Effect {
+    // uniforms: radius in pixels
+    Blur { radius: f32 },
+    // uniforms: threshold, intensity and radius in pixels
+    Bloom { threshold: f32, intensity: f32, radius: f32 },
+    // uniforms: strength from 0 to 1 and radius where it starts
+    Vignette { strength: f32, radius: f32 },
+    // uniforms: the number of scanlines and the curvature of the screen
+    Crt { scanlines: f32, curvature: f32 },
+    // a lookup table, a strip of size slices with size * size pixels,
+    // red goes right, green goes down and blue goes from slice to slice
+    ColorGrade(Image),
+    Grayscale,
+    // uniforms: size of a pixel
+    Pixelate { size: f32 },
+    // the source code of a fragment shader
+    Custom(String),
+}
+
+impl Effect {
+    // the fragment shader and the start values of its uniforms
+    fn source(&self) -> (&str, Vec<(&'static str, Uniform)>) {
+        match self {
+            Effect::Blur {radius} => (BLUR_SOURCE, vec![("radius", Uniform::Float(*radius))]),
+            Effect::Bloom {threshold, intensity, radius} => (BLOOM_SOURCE, vec![
+                ("threshold", Uniform::Float(*threshold)),
+                ("intensity", Uniform::Float(*intensity)),
+                ("radius", Uniform::Float(*radius)),
+            ]),
+            Effect::Vignette {strength, radius} => (VIGNETTE_SOURCE, vec![
+                ("strength", Uniform::Float(*strength)),
+                ("radius", Uniform::Float(*radius)),
+            ]),
+            Effect::Crt {scanlines, curvature} => (CRT_SOURCE, vec![
+                ("scanlines", Uniform::Float(*scanlines)),
+                ("curvature", Uniform::Float(*curvature)),
+            ]),
+            Effect::ColorGrade(_) => (COLOR_GRADE_SOURCE, vec![("lut", Uniform::Int(1))]),
+            Effect::Grayscale => (GRAYSCALE_SOURCE, vec![]),
+            Effect::Pixelate {size} => (PIXELATE_SOURCE, vec![("size", Uniform::Float(*size))]),
+            Effect::Custom(source) => (source, vec![]),
+        }
+    }
+}
+
+// the size of a lookup table, its width has to be its height squared
+fn lut_size(image: &Image) -> Result<f32, String> {
+    let size = image.height;
+    if size < 2.0 || image.width != size * size {
+        return Err(format!("A lookup table of {}x{} pixels needs to be {}x{}.", image.width, image.height, size * size, size));
+    }
+    Ok(size)
+}
+
+struct Pass {
+    name: String,
+    program: Program,
+    enabled: bool,
+    uniforms: HashMap<String, Uniform>,
+    lut: Option<TextureBuffer>,
+}
+
+impl Pass {
+    fn new(name: &str, effect: &Effect) -> Result<Self, String> {
+        let (source, uniforms) = effect.source();
+        let vertex_shader = Shader::new(VERTEX_SHADER_SOURCE, gl::VERTEX_SHADER)?;
+        let fragment_shader = Shader::new(source, gl::FRAGMENT_SHADER)?;
+        let program = Program::new(&vertex_shader, &fragment_shader)?;
+
+        let mut uniforms: HashMap<String, Uniform> = uniforms.into_iter()
+            .map(|(name, uniform)| (name.to_string(), uniform))
+            .collect();
+
+        let lut = match effect {
+            Effect::ColorGrade(image) => {
+                uniforms.insert("lut_size".to_string(), Uniform::Float(lut_size(image)?));
+                let lut = TextureBuffer::new();
+                lut.set_data(&image.to_rgba_image());
+                // the lut is interpolated between the colors
+                unsafe {
+                    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+                    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+                }
+                Some(lut)
+            },
+            _ => None,
+        };
+
+        Ok(Self {name: name.to_string(), program, enabled: true, uniforms, lut})
+    }
+
+    // bind the program, the lut and send all uniforms
+    fn bind(&self, resolution: (f32, f32), time: f32) -> Result<(), String> {
+        self.program.bind();
+
+        if let Some(lut) = &self.lut {
+            unsafe {
+                gl::ActiveTexture(gl::TEXTURE1);
+                lut.bind();
+                gl::ActiveTexture(gl::TEXTURE0);
+            }
+        }
+
+        Uniform::from(resolution).set(self.program.get_uniform_location("resolution")?);
+        Uniform::from(time).set(self.program.get_uniform_location("time")?);
+        for (name, uniform) in self.uniforms.iter() {
+            uniform.set(self.program.get_uniform_location(name)?);
+        }
+
+        Ok(())
+    }
+}
+
+/**
+a chain of full screen passes that is applied to the whole frame
+return it from Runtime::post_process and the engine draws every frame
+into it and runs the enabled passes in the order they were added
+```rust
+// in load
+let mut post = PostProcess::new(1270, 700).unwrap();
+post.add("bloom", Effect::Bloom {threshold: 0.7, intensity: 1.5, radius: 6.0}).unwrap();
+post.add("crt", Effect::Crt {scanlines: 240.0, curvature: 0.05}).unwrap();
+
+// in update
+post.set_uniform("crt", "curvature", 0.1).unwrap();
+post.set_enabled("bloom", false).unwrap();
+```
+*/
+pub struct PostProcess {
+    scene: RenderTarget,
+    ping: RenderTarget,
+    pong: RenderTarget,
+    vertex_array: VertexArray,
+    copy: Pass,
+    passes: Vec<Pass>,
+    start: Instant,
+}
+
+impl PostProcess {
+    // create the offscreen targets with the size of the window
+    pub fn new(width: u32, height: u32) -> Result<Self, String> {
+        Ok(Self {
+            scene: RenderTarget::new(width, height, false)?,
+            ping: RenderTarget::new(width, height, false)?,
+            pong: RenderTarget::new(width, height, false)?,
+            vertex_array: VertexArray::new(),
+            copy: Pass::new("copy", &Effect::Custom(COPY_SOURCE.to_string()))?,
+            passes: vec![],
+            start: Instant::now(),
+        })
+    }
+
+    // add a pass to the end of the chain
+    pub fn add(&mut self, name: &str, effect: Effect) -> Result<(), String> {
+        if self.passes.iter().any(|pass| pass.name == name) {
+            return Err(format!("A post processing pass with the name '{}' already exists.", name));
+        }
+
+        self.passes.push(Pass::new(name, &effect)?);
+        Ok(())
+    }
+
+    // remove a pass from the chain
+    pub fn remove(&mut self, name: &str) -> Result<(), String> {
+        let i = self.index(name)?;
+        self.passes.remove(i);
+        Ok(())
+    }
+
+    // turn a pass on or off
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> Result<(), String> {
+        let i = self.index(name)?;
+        self.passes[i].enabled = enabled;
+        Ok(())
+    }
+
+    // returns true if the pass is turned on
+    pub fn enabled(&self, name: &str) -> Result<bool, String> {
+        let i = self.index(name)?;
+        Ok(self.passes[i].enabled)
+    }
+
+    // set a uniform of a pass, it is sent every frame
+    pub fn set_uniform(&mut self, name: &str, uniform: &str, value: impl Into<Uniform>) -> Result<(), String> {
+        let i = self.index(name)?;
+        self.passes[i].uniforms.insert(uniform.to_string(), value.into());
+        Ok(())
+    }
+
+    // the names of all passes in order
+    pub fn names(&self) -> Vec<String> {
+        self.passes.iter().map(|pass| pass.name.clone()).collect()
+    }
+
+    /*
+    start drawing the frame into the chain,
+    the targets follow the size of the window
+    */
+    pub fn begin(&mut self, draw: &Draw) -> Result<(), String> {
+        let (width, height) = (draw.window.width as u32, draw.window.height as u32);
+
+        // a minimized window has no size, keep the old targets then
+        if width > 0 && height > 0 && (width, height) != (self.scene.width(), self.scene.height()) {
+            self.scene.resize(width, height)?;
+            self.ping.resize(width, height)?;
+            self.pong.resize(width, height)?;
+        }
+
+        self.scene.bind(draw);
+        Ok(())
+    }
+
+    // run all enabled passes and draw the result to the screen
+    pub fn end(&mut self, draw: &Draw) -> Result<(), String> {
+        let resolution = (self.scene.width() as f32, self.scene.height() as f32);
+        let time = self.start.elapsed().as_secs_f32();
+
+        let mut passes: Vec<&Pass> = self.passes.iter().filter(|pass| pass.enabled).collect();
+        if passes.is_empty() {
+            passes.push(&self.copy);
+        }
+
+        unsafe {
+            // every pass writes all pixels, nothing to blend with
+            gl::Disable(gl::BLEND);
+        }
+        self.vertex_array.bind();
+
+        let mut source = &self.scene;
+        for (i, pass) in passes.iter().enumerate() {
+            // the last pass draws to whatever was bound before begin, usually the screen
+            let target = if i + 1 == passes.len() {
+                self.scene.unbind(draw);
+                None
+            } else {
+                let target = if i % 2 == 0 { &self.ping } else { &self.pong };
+                target.bind(draw);
+                Some(target)
+            };
+
+            // leave the screen bound and blending on for the next frame
+            if let Err(e) = pass.bind(resolution, time) {
+                self.scene.unbind(draw);
+                unsafe {
+                    gl::Enable(gl::BLEND);
+                }
+                return Err(e);
+            }
+            source.texture().bind();
+            unsafe {
+                gl::DrawArrays(gl::TRIANGLES, 0, 3);
+            }
+
+            if let Some(target) = target {
+                source = target;
+            }
+        }
+
+        unsafe {
+            gl::Enable(gl::BLEND);
+        }
+
+        Ok(())
+    }
+
+    // find a pass by its name
+    fn index(&self, name: &str) -> Result<usize, String> {
+        match self.passes.iter().position(|pass| pass.name == name) {
+            Some(i) => Ok(i),
+            None => Err(format!("No post processing pass with the name '{}' found.", name)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lut_size() {
+        let lut = Image::from(image::RgbaImage::new(256, 16));
+        assert_eq!(Ok(16.0), lut_size(&lut));
+
+        let lut = Image::from(image::RgbaImage::new(64, 16));
+        assert!(lut_size(&lut).is_err());
+    }
+
+    #[test]
+    fn test_effect_uniforms() {
+        let bloom = Effect::Bloom {threshold: 0.7, intensity: 1.5, radius: 6.0};
+        let (_, uniforms) = bloom.source();
+        assert_eq!(vec![
+            ("threshold", Uniform::Float(0.7)),
+            ("intensity", Uniform::Float(1.5)),
+            ("radius", Uniform::Float(6.0)),
+        ], uniforms);
+
+        let custom = Effect::Custom("#version 330".to_string());
+        let (source, uniforms) = custom.source();
+        assert_eq!("#version 330", source);
+        assert!(uniforms.is_empty());
+    }
+}
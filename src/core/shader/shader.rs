@@ -1,9 +1,14 @@
 use crate::core::shader::object::{Object, ObjectState};
 use crate::core::*;
-use crate::core::shader::object::{circle::Circle, rect::Rect, text::Text, texture::Texture};
+use crate::core::shader::object::{circle::Circle, rect::Rect, text::Text, texture::Texture, mesh::Mesh};
 use crate::core::color::Color;
 use crate::core::resource::font::Font;
 use crate::core::shader::object::TextureCoordinate;
+use crate::core::shader::data::{Gradient, Shadow};
+use crate::core::shader::batch::{self, SpriteBatch, DrawOrder};
+use crate::core::shader::target::RenderTarget;
+use crate::core::shader::material::Material;
+use crate::core::math::shape::{self, Cap, Join};
 
 /// The api to draw to the screen
 /// 
@@ -84,6 +89,108 @@ impl Shader {
         Ok(component)
     }
 
+    /*
+    create a new Shader out of triangles, three points form one triangle.
+    the points are in pixels, the offset and the dim of the Shader
+    are the bounding box of the triangles
+    */
+    pub fn mesh(triangles: &[(f32, f32)]) -> Result<Self, String> {
+        if triangles.is_empty() || !triangles.len().is_multiple_of(3) {
+            return Err(format!("Cannot create a mesh out of {} points.", triangles.len()));
+        }
+
+        // move the triangles into the range 0 to 1
+        let (mut min, mut max) = (triangles[0], triangles[0]);
+        for (x, y) in triangles.iter() {
+            min = (min.0.min(*x), min.1.min(*y));
+            max = (max.0.max(*x), max.1.max(*y));
+        }
+        let dim = (max.0 - min.0, max.1 - min.1);
+        let vertices: Vec<(f32, f32)> = triangles.iter().map(|(x, y)| {
+            ((x - min.0) / dim.0.max(f32::EPSILON), (y - min.1) / dim.1.max(f32::EPSILON))
+        }).collect();
+
+        let object_data = ObjectData {
+            offset: min,
+            dim,
+            ..ObjectData::default()
+        };
+
+        let mut mesh = Mesh::new(&vertices);
+        mesh.add(&object_data);
+        mesh.load()?;
+
+        let component = Self {
+            object: Box::new(mesh),
+            object_data, 
+            transform: Transform::default(),
+        };
+
+        Ok(component)
+    }
+
+    // create a new line Shader between two points
+    pub fn line(from: (f32, f32), to: (f32, f32), thickness: f32, cap: Cap) -> Result<Self, String> {
+        Self::mesh(&shape::polyline(&[from, to], thickness, cap, Join::Miter, false))
+    }
+
+    // create a new line Shader along points
+    pub fn polyline(points: &[(f32, f32)], thickness: f32, cap: Cap, join: Join) -> Result<Self, String> {
+        Self::mesh(&shape::polyline(points, thickness, cap, join, false))
+    }
+
+    // create a new filled polygon Shader, it can be concave
+    pub fn polygon(points: &[(f32, f32)]) -> Result<Self, String> {
+        Self::mesh(&shape::polygon(points)?)
+    }
+
+    // create a new Shader with the outline of a polygon
+    pub fn polygon_outline(points: &[(f32, f32)], thickness: f32, join: Join) -> Result<Self, String> {
+        Self::mesh(&shape::polygon_outline(points, thickness, join))
+    }
+
+    // create a new arc Shader, angles are in radians and go clockwise
+    pub fn arc(center: (f32, f32), radius: f32, start: f32, end: f32, thickness: f32) -> Result<Self, String> {
+        Self::mesh(&shape::arc_outline(center, radius, start, end, thickness))
+    }
+
+    // create a new pie slice Shader, angles are in radians and go clockwise
+    pub fn pie(center: (f32, f32), radius: f32, start: f32, end: f32) -> Result<Self, String> {
+        Self::mesh(&shape::pie(center, radius, start, end))
+    }
+
+    // create a new Shader with the outline of a rect
+    pub fn rect_outline(width: f32, height: f32, thickness: f32) -> Result<Self, String> {
+        Self::mesh(&shape::rect_outline(width, height, thickness))
+    }
+
+    // create a new Shader with the outline of a circle
+    pub fn circle_outline(radius: f32, thickness: f32) -> Result<Self, String> {
+        Self::mesh(&shape::circle_outline(radius, thickness))
+    }
+
+    // create a new Shader that draws the texture of a render target
+    pub fn target(target: &RenderTarget) -> Result<Self, String> {
+        // create the data that is used to create 
+        // the transform buffer in the shader
+        let object_data = ObjectData {
+            dim: (target.width() as f32, target.height() as f32),
+            ..ObjectData::default()
+        };
+
+        let mut texture = Texture::from_buffer(target.texture());
+        texture.add(&object_data);
+        texture.load()?;
+
+        let component = Self {
+            object: Box::new(texture),
+            object_data, 
+            transform: Transform::default(),
+        };
+
+        Ok(component)
+    }
+
     // create a new text Shader
     pub fn text(text: &str, font: &Font, font_size: usize, color: &Color) -> Result<Self, String> {
         // create the text as rgba image
@@ -116,6 +223,29 @@ impl Shader {
         Ok(())
     } 
 
+    // draw the Shader with a material instead of its own program,
+    // a material is not used when the Shader is submitted to a SpriteBatch
+    pub fn set_material(&mut self, material: &Material) -> Result<(), String> {
+        self.object.set_material(Some(material.clone()))
+    }
+
+    // draw the Shader with its own program again
+    pub fn remove_material(&mut self) -> Result<(), String> {
+        self.object.set_material(None)
+    }
+
+    // submit the Shader to a SpriteBatch instead of drawing it
+    pub fn submit(&self, batch: &mut SpriteBatch, draw_order: impl Into<DrawOrder>) -> Result<(), String> {
+        match self.object.batch_kind() {
+            Some(kind) => {
+                batch::check(&kind, &self.object_data)?;
+                batch.push(kind, &self.object_data, &self.transform, draw_order);
+                Ok(())
+            },
+            None => Err("This Shader cannot be drawn by a SpriteBatch.".to_string()),
+        }
+    }
+
     // set the width and the height of the Shader
     pub fn set_dim(&mut self, width: f32, height: f32) {
         self.object_data.dim.0 = width;
@@ -204,6 +334,55 @@ impl Shader {
         self.object_data.texcoord
     }
 
+    // set the corner radius of a rect Shader
+    pub fn set_radius(&mut self, radius: f32) {
+        self.object_data.radius = radius;
+        self.object.set(0, &self.object_data);
+        self.object.set_state(ObjectState::Reload);
+    }
+
+    // get the corner radius of the Shader
+    pub fn radius(&self) -> f32 {
+        self.object_data.radius
+    }
+
+    // set the border width and color of a rect Shader, drawn inside the rect
+    pub fn set_border(&mut self, width: f32, color: &Color) {
+        self.object_data.border = width;
+        self.object_data.border_color = *color;
+        self.object.set(0, &self.object_data);
+        self.object.set_state(ObjectState::Reload);
+    }
+
+    // get the border width and color of the Shader
+    pub fn border(&self) -> (f32, Color) {
+        (self.object_data.border, self.object_data.border_color)
+    }
+
+    // set the gradient of a rect Shader, it goes from the color to the gradient color
+    pub fn set_gradient(&mut self, gradient: Gradient) {
+        self.object_data.gradient = gradient;
+        self.object.set(0, &self.object_data);
+        self.object.set_state(ObjectState::Reload);
+    }
+
+    // get the gradient of the Shader
+    pub fn gradient(&self) -> Gradient {
+        self.object_data.gradient
+    }
+
+    // set the drop shadow of a rect Shader
+    pub fn set_shadow(&mut self, shadow: Option<Shadow>) {
+        self.object_data.shadow = shadow;
+        self.object.set(0, &self.object_data);
+        self.object.set_state(ObjectState::Reload);
+    }
+
+    // get the drop shadow of the Shader
+    pub fn shadow(&self) -> Option<Shadow> {
+        self.object_data.shadow
+    }
+
     // implement collision on 
     // both Shader types
     pub fn collides(&self, x: f32, y: f32) -> bool {
@@ -0,0 +1,219 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use gl::types::*;
+use crate::core::shader::object::TextureBuffer;
+use crate::core::resource::image::Image;
+use crate::core::color::Color;
+use crate::core::project::Draw;
+use crate::core::window::Window;
+
+/**
+an offscreen framebuffer with a color texture
+and an optional depth and stencil buffer
+
+everything drawn between bind and unbind ends up in the texture,
+which can be drawn with Shader::target like any other texture
+```rust
+// in load
+let target = RenderTarget::new(320, 180, false).unwrap();
+let mut screen = Shader::target(&target).unwrap();
+
+// in update
+let target_draw = target.bind(&draw);
+target.clear(&Color::black());
+player.draw(&target_draw, &camera).unwrap();
+target.unbind(&draw);
+
+screen.draw(&draw, &camera).unwrap();
+```
+*/
+pub struct RenderTarget {
+    framebuffer: GLuint,
+    depth_stencil: Option<GLuint>,
+    texture: Rc<TextureBuffer>,
+    width: u32,
+    height: u32,
+    // the framebuffer and viewport that were bound before bind, unbind restores them
+    previous: Cell<(GLint, [GLint; 4])>,
+}
+
+impl RenderTarget {
+    // create a render target with the given size in pixels
+    pub fn new(width: u32, height: u32, depth_stencil: bool) -> Result<Self, String> {
+        if width == 0 || height == 0 {
+            return Err(format!("Cannot create a render target with the size {}x{}.", width, height));
+        }
+
+        let mut framebuffer: GLuint = 0;
+        let texture = TextureBuffer::new();
+        texture.allocate(width, height);
+
+        // a target can be created while another one is bound
+        let mut bound: GLint = 0;
+        unsafe {
+            gl::GetIntegerv(gl::FRAMEBUFFER_BINDING, &mut bound);
+            gl::GenFramebuffers(1, &mut framebuffer);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer);
+            gl::FramebufferTexture2D(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0, gl::TEXTURE_2D, texture.id, 0);
+        }
+
+        let mut target = Self {
+            framebuffer,
+            depth_stencil: None,
+            texture: Rc::new(texture),
+            width, height,
+            previous: Cell::new((0, [0; 4])),
+        };
+
+        if depth_stencil {
+            let mut renderbuffer: GLuint = 0;
+            unsafe {
+                gl::GenRenderbuffers(1, &mut renderbuffer);
+                gl::BindRenderbuffer(gl::RENDERBUFFER, renderbuffer);
+                gl::RenderbufferStorage(gl::RENDERBUFFER, gl::DEPTH24_STENCIL8, width as i32, height as i32);
+                gl::FramebufferRenderbuffer(gl::FRAMEBUFFER, gl::DEPTH_STENCIL_ATTACHMENT, gl::RENDERBUFFER, renderbuffer);
+            }
+            target.depth_stencil = Some(renderbuffer);
+        }
+
+        let status = unsafe {
+            let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, bound as GLuint);
+            status
+        };
+
+        if status != gl::FRAMEBUFFER_COMPLETE {
+            return Err(format!("The render target is not complete, status: {:#x}.", status));
+        }
+
+        Ok(target)
+    }
+
+    /*
+    draw into the render target until unbind is called
+    returns a copy of draw with the size of the target as window,
+    pass it to the draw functions so they use the right projection.
+    the framebuffer that was bound before is remembered, so targets
+    can be used while a PostProcess draws the scene into its own target
+    */
+    pub fn bind(&self, draw: &Draw) -> Draw {
+        unsafe {
+            let mut framebuffer: GLint = 0;
+            let mut viewport: [GLint; 4] = [0; 4];
+            gl::GetIntegerv(gl::FRAMEBUFFER_BINDING, &mut framebuffer);
+            gl::GetIntegerv(gl::VIEWPORT, viewport.as_mut_ptr());
+            self.previous.set((framebuffer, viewport));
+
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.framebuffer);
+            gl::Viewport(0, 0, self.width as i32, self.height as i32);
+        }
+
+        let mut target_draw = draw.clone();
+        target_draw.window = Window::new(self.width as f32, self.height as f32);
+        target_draw
+    }
+
+    // draw to the framebuffer that was bound before bind again, usually the screen
+    pub fn unbind(&self, _draw: &Draw) {
+        let (framebuffer, [x, y, width, height]) = self.previous.get();
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer as GLuint);
+            gl::Viewport(x, y, width, height);
+        }
+    }
+
+    // clear the render target with a color, the target has to be bound
+    pub fn clear(&self, color: &Color) {
+        unsafe {
+            // keep the clear color of the screen
+            let mut clear_color = [0.0; 4];
+            gl::GetFloatv(gl::COLOR_CLEAR_VALUE, clear_color.as_mut_ptr());
+
+            gl::ClearColor(color.r, color.g, color.b, 1.0);
+            let mut mask = gl::COLOR_BUFFER_BIT;
+            if self.depth_stencil.is_some() {
+                mask |= gl::DEPTH_BUFFER_BIT | gl::STENCIL_BUFFER_BIT;
+            }
+            gl::Clear(mask);
+
+            gl::ClearColor(clear_color[0], clear_color[1], clear_color[2], clear_color[3]);
+        }
+    }
+
+    // change the size of the render target, this clears its content
+    pub fn resize(&mut self, width: u32, height: u32) -> Result<(), String> {
+        if width == 0 || height == 0 {
+            return Err(format!("Cannot resize a render target to {}x{}.", width, height));
+        }
+
+        self.width = width;
+        self.height = height;
+        self.texture.allocate(width, height);
+
+        if let Some(renderbuffer) = self.depth_stencil {
+            unsafe {
+                gl::BindRenderbuffer(gl::RENDERBUFFER, renderbuffer);
+                gl::RenderbufferStorage(gl::RENDERBUFFER, gl::DEPTH24_STENCIL8, width as i32, height as i32);
+            }
+        }
+
+        Ok(())
+    }
+
+    /*
+    read the pixels of the render target back into an image
+    the rows start at the bottom like the rows of a loaded image,
+    flip it before saving it to a file
+    */
+    pub fn to_image(&self) -> Result<Image, String> {
+        let mut pixels = vec![0u8; (self.width * self.height * 4) as usize];
+
+        unsafe {
+            // read from the target without changing what is drawn to
+            let mut framebuffer: GLint = 0;
+            gl::GetIntegerv(gl::FRAMEBUFFER_BINDING, &mut framebuffer);
+
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.framebuffer);
+            gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+            gl::ReadPixels(
+                0, 0,
+                self.width as i32, self.height as i32,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                pixels.as_mut_ptr() as *mut GLvoid,
+            );
+            gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer as GLuint);
+        }
+
+        match image::RgbaImage::from_raw(self.width, self.height, pixels) {
+            Some(image) => Ok(Image::from(image)),
+            None => Err("Could not read the pixels of the render target.".to_string()),
+        }
+    }
+
+    // the color texture, shared with every Shader that draws the target
+    pub fn texture(&self) -> Rc<TextureBuffer> {
+        self.texture.clone()
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+}
+
+// delete the framebuffer on the graphics card,
+// the texture lives on while a Shader still uses it
+impl Drop for RenderTarget {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteFramebuffers(1, [self.framebuffer].as_ptr());
+            if let Some(renderbuffer) = self.depth_stencil {
+                gl::DeleteRenderbuffers(1, [renderbuffer].as_ptr());
+            }
+        }
+    }
+}
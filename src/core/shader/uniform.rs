@@ -0,0 +1,72 @@
+use gl::types::*;
+use nalgebra_glm::Mat4;
+use crate::core::color::Color;
+
+// a value that can be sent to a uniform of a shader program
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Uniform {
+    Int(i32),
+    Float(f32),
+    Vec2(f32, f32),
+    Vec3(f32, f32, f32),
+    Vec4(f32, f32, f32, f32),
+    Mat4(Mat4),
+}
+
+impl Uniform {
+    // send the value to a location of the bound program
+    pub fn set(&self, location: GLint) {
+        unsafe {
+            match *self {
+                Uniform::Int(v) => gl::Uniform1i(location, v),
+                Uniform::Float(v) => gl::Uniform1f(location, v),
+                Uniform::Vec2(x, y) => gl::Uniform2f(location, x, y),
+                Uniform::Vec3(x, y, z) => gl::Uniform3f(location, x, y, z),
+                Uniform::Vec4(x, y, z, w) => gl::Uniform4f(location, x, y, z, w),
+                Uniform::Mat4(m) => gl::UniformMatrix4fv(location, 1, gl::FALSE, m.as_ptr()),
+            }
+        }
+    }
+}
+
+impl From<i32> for Uniform {
+    fn from(v: i32) -> Self {
+        Uniform::Int(v)
+    }
+}
+
+impl From<f32> for Uniform {
+    fn from(v: f32) -> Self {
+        Uniform::Float(v)
+    }
+}
+
+impl From<(f32, f32)> for Uniform {
+    fn from((x, y): (f32, f32)) -> Self {
+        Uniform::Vec2(x, y)
+    }
+}
+
+impl From<(f32, f32, f32)> for Uniform {
+    fn from((x, y, z): (f32, f32, f32)) -> Self {
+        Uniform::Vec3(x, y, z)
+    }
+}
+
+impl From<(f32, f32, f32, f32)> for Uniform {
+    fn from((x, y, z, w): (f32, f32, f32, f32)) -> Self {
+        Uniform::Vec4(x, y, z, w)
+    }
+}
+
+impl From<Mat4> for Uniform {
+    fn from(m: Mat4) -> Self {
+        Uniform::Mat4(m)
+    }
+}
+
+impl From<Color> for Uniform {
+    fn from(color: Color) -> Self {
+        Uniform::Vec4(color.r, color.g, color.b, color.a)
+    }
+}
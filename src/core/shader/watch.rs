@@ -0,0 +1,87 @@
+use std::fs;
+use std::time::SystemTime;
+use crate::core::file;
+
+/*
+a shader source that is loaded from a file
+and can be polled for changes, used for hot reloading
+*/
+pub struct SourceFile {
+    path: String,
+    modified: Option<SystemTime>,
+    source: String,
+}
+
+impl SourceFile {
+    // read a source file
+    pub fn new(path: &str) -> Result<Self, String> {
+        Ok(Self {
+            path: path.to_string(),
+            modified: modified(path),
+            source: file::read(path)?,
+        })
+    }
+
+    /*
+    check the modification time of the file and read it again
+    when it changed, returns the new source in that case
+    */
+    pub fn poll(&mut self) -> Result<Option<&str>, String> {
+        let modified = modified(&self.path);
+        if modified == self.modified {
+            return Ok(None);
+        }
+
+        // only check again after the next change, even if reading fails
+        self.modified = modified;
+        self.source = file::read(&self.path)?;
+        Ok(Some(&self.source))
+    }
+
+    // forget the modification time, the next poll reads the file again
+    pub fn forget(&mut self) {
+        self.modified = None;
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+}
+
+// the modification time of a file, None if it cannot be read
+fn modified(path: &str) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_poll() {
+        let path = std::env::temp_dir().join("d7engine_test_poll.frag");
+        let path = path.to_str().unwrap();
+        file::write(path, "void main() {}").unwrap();
+
+        let mut source = SourceFile::new(path).unwrap();
+        assert_eq!("void main() {}", source.source());
+        assert_eq!(Ok(None), source.poll());
+
+        // set the time by hand, the file system might be too coarse
+        file::write(path, "void main() { color = vec4(1.0); }").unwrap();
+        let later = SystemTime::now() + Duration::from_secs(10);
+        fs::File::options().write(true).open(path).unwrap().set_modified(later).unwrap();
+
+        assert_eq!(Ok(Some("void main() { color = vec4(1.0); }")), source.poll());
+        assert_eq!(Ok(None), source.poll());
+
+        fs::remove_file(path).unwrap();
+        assert!(source.poll().is_err());
+        assert!(SourceFile::new(path).is_err());
+    }
+}
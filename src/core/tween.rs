@@ -0,0 +1,353 @@
+use crate::core::color::Color;
+use crate::core::math::easing::Easing;
+use crate::core::math::linear::lerp;
+use crate::core::math::transform::Transform;
+
+/**
+Implement Tweenable for a value,
+so it can be animated by a Tween.
+*/
+pub trait Tweenable: Clone {
+    /// the value between self (t = 0.0) and to (t = 1.0),
+    /// t can be outside of 0.0 to 1.0 for easings that overshoot
+    fn tween(&self, to: &Self, t: f32) -> Self;
+}
+
+impl Tweenable for f32 {
+    fn tween(&self, to: &Self, t: f32) -> Self {
+        lerp(*self, *to, t)
+    }
+}
+
+impl Tweenable for (f32, f32) {
+    fn tween(&self, to: &Self, t: f32) -> Self {
+        (lerp(self.0, to.0, t), lerp(self.1, to.1, t))
+    }
+}
+
+impl Tweenable for (f32, f32, f32) {
+    fn tween(&self, to: &Self, t: f32) -> Self {
+        (lerp(self.0, to.0, t), lerp(self.1, to.1, t), lerp(self.2, to.2, t))
+    }
+}
+
+impl Tweenable for Color {
+    fn tween(&self, to: &Self, t: f32) -> Self {
+        Color {
+            r: lerp(self.r, to.r, t),
+            g: lerp(self.g, to.g, t),
+            b: lerp(self.b, to.b, t),
+            a: lerp(self.a, to.a, t),
+        }
+    }
+}
+
+/*
+animates a value from one value to another over duration seconds
+
+the tween is advanced with the delta time of a frame and returns
+the current value, e.g. in update of the runtime:
+    data.opacity = self.fade.update(draw.performance.delta());
+
+the tween can wait before it starts (delay), play more than once (repeat)
+and play every second time backwards (yoyo)
+*/
+pub struct Tween<T: Tweenable> {
+    from: T,
+    to: T,
+    duration: f32,
+    delay: f32,
+    easing: Easing,
+    yoyo: bool,
+    // None plays forever
+    plays: Option<usize>,
+    elapsed: f32,
+    on_complete: Option<Box<dyn FnMut()>>,
+}
+
+impl<T: Tweenable> Tween<T> {
+    // create a linear tween that plays once
+    pub fn new(from: T, to: T, duration: f32) -> Tween<T> {
+        Tween {
+            from, to,
+            duration: duration.max(0.0),
+            delay: 0.0,
+            easing: Easing::Linear,
+            yoyo: false,
+            plays: Some(1),
+            elapsed: 0.0,
+            on_complete: None,
+        }
+    }
+
+    pub fn easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    // wait seconds before the tween starts
+    pub fn delay(mut self, seconds: f32) -> Self {
+        self.delay = seconds.max(0.0);
+        self
+    }
+
+    // play the tween count more times after the first time
+    pub fn repeat(mut self, count: usize) -> Self {
+        self.plays = Some(count + 1);
+        self
+    }
+
+    // play the tween until it is stopped
+    pub fn forever(mut self) -> Self {
+        self.plays = None;
+        self
+    }
+
+    // every second play goes from to back to from
+    pub fn yoyo(mut self, yoyo: bool) -> Self {
+        self.yoyo = yoyo;
+        self
+    }
+
+    // called once when the tween is finished
+    pub fn on_complete<F: FnMut() + 'static>(mut self, callback: F) -> Self {
+        self.on_complete = Some(Box::new(callback));
+        self
+    }
+
+    // advance the tween by delta seconds and return the current value
+    pub fn update(&mut self, delta: f32) -> T {
+        let was_finished = self.finished();
+        self.elapsed += delta.max(0.0);
+
+        if !was_finished && self.finished() {
+            if let Some(callback) = self.on_complete.as_mut() {
+                callback();
+            }
+        }
+
+        self.value()
+    }
+
+    // the current value without advancing the tween
+    pub fn value(&self) -> T {
+        let time = self.elapsed - self.delay;
+
+        if time <= 0.0 {
+            return self.from.clone();
+        }
+
+        // the index of the current play and the progress in it
+        let (play, progress) = if self.finished() {
+            (self.plays.unwrap_or(1) - 1, 1.0)
+        } else if self.duration <= 0.0 {
+            (0, 1.0)
+        } else {
+            let play = (time / self.duration).floor();
+            (play as usize, (time - play * self.duration) / self.duration)
+        };
+
+        let progress = if self.yoyo && play % 2 == 1 { 1.0 - progress } else { progress };
+        self.from.tween(&self.to, self.easing.apply(progress))
+    }
+
+    // true if all plays are done, a tween that plays forever is never finished
+    pub fn finished(&self) -> bool {
+        match self.plays {
+            Some(plays) => self.elapsed - self.delay >= self.duration * plays as f32,
+            None => false,
+        }
+    }
+
+    // the seconds after the tween was finished, used to carry over time
+    fn overflow(&self) -> f32 {
+        match self.plays {
+            Some(plays) => (self.elapsed - self.delay - self.duration * plays as f32).max(0.0),
+            None => 0.0,
+        }
+    }
+
+    // start the tween from the beginning
+    pub fn reset(&mut self) {
+        self.elapsed = 0.0;
+    }
+}
+
+impl Tween<(f32, f32, f32)> {
+    // advance the tween and set the position of a transform
+    pub fn position(&mut self, transform: &mut Transform, delta: f32) {
+        let (x, y, z) = self.update(delta);
+        transform.set(x, y, z);
+    }
+
+    // advance the tween and set the scale of a transform
+    pub fn scale(&mut self, transform: &mut Transform, delta: f32) {
+        let (x, y, z) = self.update(delta);
+        transform.set_scale(x, y, z);
+    }
+}
+
+impl Tween<f32> {
+    // advance the tween and set the rotation around the z axis in radians
+    pub fn rotation(&mut self, transform: &mut Transform, delta: f32) {
+        let angle = self.update(delta);
+        transform.set_rotation(angle, 0.0, 0.0, 1.0);
+    }
+}
+
+/*
+plays tweens of the same value one after another,
+time that is left at the end of one tween goes to the next one
+*/
+pub struct Sequence<T: Tweenable> {
+    tweens: Vec<Tween<T>>,
+    current: usize,
+    on_complete: Option<Box<dyn FnMut()>>,
+}
+
+impl<T: Tweenable> Sequence<T> {
+    pub fn new() -> Sequence<T> {
+        Sequence {tweens: vec![], current: 0, on_complete: None}
+    }
+
+    // add a tween at the end
+    pub fn then(mut self, tween: Tween<T>) -> Self {
+        self.tweens.push(tween);
+        self
+    }
+
+    // called once when the last tween is finished
+    pub fn on_complete<F: FnMut() + 'static>(mut self, callback: F) -> Self {
+        self.on_complete = Some(Box::new(callback));
+        self
+    }
+
+    // advance the current tween by delta seconds and return its value,
+    // None if the sequence has no tweens
+    pub fn update(&mut self, delta: f32) -> Option<T> {
+        let was_finished = self.finished();
+        let mut delta = delta;
+        let last = self.tweens.len().saturating_sub(1);
+
+        while let Some(tween) = self.tweens.get_mut(self.current) {
+            tween.update(delta);
+
+            if !tween.finished() || self.current == last {
+                break;
+            }
+
+            delta = tween.overflow();
+            self.current += 1;
+        }
+
+        if !was_finished && self.finished() {
+            if let Some(callback) = self.on_complete.as_mut() {
+                callback();
+            }
+        }
+
+        self.value()
+    }
+
+    // the value of the current tween
+    pub fn value(&self) -> Option<T> {
+        self.tweens.get(self.current).map(|tween| tween.value())
+    }
+
+    pub fn finished(&self) -> bool {
+        match self.tweens.last() {
+            Some(last) => self.current + 1 == self.tweens.len() && last.finished(),
+            None => true,
+        }
+    }
+
+    // start the sequence from the beginning
+    pub fn reset(&mut self) {
+        self.current = 0;
+        for tween in self.tweens.iter_mut() {
+            tween.reset();
+        }
+    }
+}
+
+impl<T: Tweenable> Default for Sequence<T> {
+    fn default() -> Self {
+        Sequence::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+    use std::cell::Cell;
+
+    #[test]
+    fn test_tween() {
+        let mut tween = Tween::new(0.0, 10.0, 2.0).delay(1.0);
+        assert_eq!(0.0, tween.update(0.5));
+        assert_eq!(0.0, tween.update(0.5));
+        assert_eq!(5.0, tween.update(1.0));
+        assert_eq!(10.0, tween.update(5.0));
+        assert!(tween.finished());
+
+        let mut tween = Tween::new((0.0, 0.0), (4.0, 8.0), 1.0).easing(Easing::QuadIn);
+        assert_eq!((1.0, 2.0), tween.update(0.5));
+
+        let mut tween = Tween::new(Color::rgb(0, 0, 0), Color::rgb(255, 255, 255), 1.0);
+        assert_eq!(0.5, tween.update(0.5).g);
+    }
+
+    #[test]
+    fn test_yoyo_repeat() {
+        let mut tween = Tween::new(0.0, 10.0, 1.0).yoyo(true).repeat(2);
+        assert_eq!(5.0, tween.update(0.5));
+        assert_eq!(7.5, tween.update(0.75));
+        assert_eq!(2.5, tween.update(1.0));
+        assert!(!tween.finished());
+        assert_eq!(10.0, tween.update(1.0));
+        assert!(tween.finished());
+
+        let mut tween = Tween::new(0.0, 10.0, 1.0).yoyo(true).forever();
+        assert_eq!(0.0, tween.update(100.0));
+        assert!(!tween.finished());
+    }
+
+    #[test]
+    fn test_callbacks_and_transform() {
+        let count = Rc::new(Cell::new(0));
+        let counter = count.clone();
+        let mut tween = Tween::new((0.0, 0.0, 0.0), (10.0, 20.0, 0.0), 1.0)
+            .on_complete(move || counter.set(counter.get() + 1));
+
+        let mut transform = Transform::new();
+        tween.position(&mut transform, 0.5);
+        assert_eq!((5.0, 10.0, 0.0), transform.pos());
+        tween.position(&mut transform, 1.0);
+        tween.position(&mut transform, 1.0);
+        assert_eq!((10.0, 20.0, 0.0), transform.pos());
+        assert_eq!(1, count.get());
+    }
+
+    #[test]
+    fn test_sequence() {
+        let done = Rc::new(Cell::new(false));
+        let flag = done.clone();
+        let mut sequence = Sequence::new()
+            .then(Tween::new(0.0, 10.0, 1.0))
+            .then(Tween::new(10.0, 0.0, 2.0))
+            .on_complete(move || flag.set(true));
+
+        assert_eq!(Some(5.0), sequence.update(0.5));
+        // the half second left goes to the second tween
+        assert_eq!(Some(7.5), sequence.update(1.0));
+        assert!(!done.get());
+        assert_eq!(Some(0.0), sequence.update(2.0));
+        assert!(sequence.finished());
+        assert!(done.get());
+
+        sequence.reset();
+        assert_eq!(Some(0.0), sequence.value());
+        assert_eq!(None, Sequence::<f32>::new().update(1.0));
+    }
+}
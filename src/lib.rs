@@ -139,6 +139,16 @@ pub fn init(config: crate::core::project::Config, runtime: &mut impl crate::core
             keys: keys,
         };
      
+        // draw into the post processing chain if the project has one,
+        // when it cannot begin the frame goes straight to the screen
+        let mut post_processing = false;
+        if let Some(post_process) = runtime.post_process() {
+            match post_process.begin(&draw) {
+                Ok(()) => post_processing = true,
+                Err(e) => eprintln!("{}", e),
+            }
+        }
+
         unsafe {
             // clear the screen
             gl::Clear(gl::COLOR_BUFFER_BIT);
@@ -146,6 +156,15 @@ pub fn init(config: crate::core::project::Config, runtime: &mut impl crate::core
 
         // call the projects draw method
         runtime.update(&draw);
+
+        // apply the post processing to the screen
+        if post_processing {
+            if let Some(post_process) = runtime.post_process() {
+                if let Err(e) = post_process.end(&draw) {
+                    eprintln!("{}", e);
+                }
+            }
+        }
         
         // sdl will change the window its draing to
         window.gl_swap_window();